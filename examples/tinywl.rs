@@ -6,8 +6,8 @@ use wlroots_sys::*;
 
 use anyhow::Result;
 use wlroots::{
-    Allocator, Backend, Compositor, DataDeviceManager, Output, OutputLayout, Renderer, Scene,
-    Subcompositor, XdgShell,
+    Allocator, Backend, Compositor, DataDeviceManager, Output, OutputLayout, Presentation,
+    Renderer, Scene, Subcompositor, Surface, XdgShell, XdgSurface, XdgSurfaceRole,
 };
 
 struct State {
@@ -15,6 +15,7 @@ struct State {
     renderer: Renderer,
     allocator: Allocator,
     output_layout: OutputLayout,
+    scene: Scene,
     xdg_shell: XdgShell,
     outputs: Vec<Output>,
 }
@@ -41,9 +42,12 @@ fn main() -> Result<()> {
     let _ = DataDeviceManager::new(&display_handle)?;
 
     let scene = Scene::new()?;
-    scene.attach_output_layout(&output_layout);
+    scene.attach_output_layout(&output_layout)?;
 
-    let xdg_shell = XdgShell::new(&display_handle, 3);
+    let presentation = Presentation::new(&display_handle, &backend)?;
+    scene.set_presentation(&presentation)?;
+
+    let xdg_shell = XdgShell::new(&display_handle, 3)?;
 
     let outputs = Vec::new();
 
@@ -52,6 +56,7 @@ fn main() -> Result<()> {
         renderer,
         allocator,
         output_layout,
+        scene,
         xdg_shell,
         outputs,
     }));
@@ -66,33 +71,64 @@ fn main() -> Result<()> {
             .expect("failed to init renderer");
 
         for mode in output.modes() {
-            eprintln!("mode: {:?}", mode.dimensions());
+            eprintln!("mode: {:?}", mode);
         }
 
         if let Some(mode) = output.preferred_mode() {
-            output.set_mode(mode);
+            output.set_mode(&mode);
             output.enable(true);
             output.commit().expect("initial commit failed");
         }
 
-        output.on_frame(|| eprintln!("frame!"));
-
-        state.output_layout.add_auto(&output);
+        // Nested backends ask for a new state when their window is resized.
+        let request_output = Output::from_ptr(output.handle().as_ptr());
+        output.on_request_state(move |event| {
+            let _ = request_output.commit_state(event.state);
+        });
+
+        state
+            .output_layout
+            .add_auto(&output)
+            .expect("failed to add output to layout");
+
+        let scene_output = state
+            .scene
+            .create_output(&output)
+            .expect("failed to create scene output");
+        scene_output
+            .render_on_frame(&mut output)
+            .expect("failed to set up render loop");
 
         state.outputs.push(output);
     });
 
-    state.borrow_mut().xdg_shell.on_new_surface(|surface| {
+    let state_clone = state.clone();
+    state.borrow_mut().xdg_shell.on_new_surface(move |surface| {
         eprintln!("new surface!");
-
-        match surface {
-            XdgSurface::Popup(popup) => {
-                let parent = XdgSurface::from_surface(popup.parent());
-                // TODO: ergonomics?
-                let tree = state.scene.create_xdg_surface(parent.data(), &surface);
-                surface.set_userdata(tree);
-                return;
+        let state = state_clone.borrow();
+
+        match surface.role() {
+            XdgSurfaceRole::Popup => {
+                // Popups are parented under their toplevel's tree, so they
+                // move along with it.
+                if let Some(parent_tree) = surface.parent_scene_tree() {
+                    parent_tree
+                        .add_xdg_surface(&surface)
+                        .expect("failed to add popup to scene");
+                }
             }
+            XdgSurfaceRole::Toplevel => {
+                let tree = state
+                    .scene
+                    .tree()
+                    .add_xdg_surface(&surface)
+                    .expect("failed to add toplevel to scene");
+
+                // Hits on any of the toplevel's surfaces can be mapped back
+                // to it with SceneHit::data; see toplevel_at.
+                tree.node().set_data(surface);
+            }
+            XdgSurfaceRole::None => {}
         }
 
         // TODO: many event handlers.
@@ -111,3 +147,15 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+/// Finds the toplevel under a point in layout coordinates, along with the
+/// surface that was hit and the point in that surface's coordinates. This is
+/// what pointer focus and click-to-focus are built on.
+// Unused until the example handles pointer input.
+#[allow(dead_code)]
+fn toplevel_at(scene: &Scene, lx: f64, ly: f64) -> Option<(Rc<XdgSurface>, Surface, f64, f64)> {
+    let hit = scene.node_at(lx, ly)?;
+    let surface = hit.surface()?;
+    let toplevel = hit.data::<XdgSurface>()?;
+    Some((toplevel, surface, hit.sx, hit.sy))
+}
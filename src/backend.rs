@@ -1,6 +1,9 @@
 use wayland_server::DisplayHandle;
 use wayland_sys::server::wl_signal;
-use wlroots_sys::{wlr_backend, wlr_backend_autocreate, wlr_backend_destroy, wlr_output};
+use wlroots_sys::{
+    wlr_backend, wlr_backend_autocreate, wlr_backend_destroy, wlr_backend_start,
+    wlr_headless_add_output, wlr_headless_backend_create, wlr_output,
+};
 
 use crate::{output::Output, Destroyable, Handle, WlrError};
 
@@ -16,6 +19,18 @@ impl Backend {
         }
     }
 
+    /// Creates a backend with no real inputs or outputs. Outputs are added
+    /// with [`Backend::add_headless_output`], and render into offscreen
+    /// buffers.
+    pub fn headless(display: &DisplayHandle) -> Result<Backend, WlrError> {
+        let ptr =
+            unsafe { wlr_headless_backend_create(display.backend_handle().display_ptr()).as_mut() };
+        match ptr {
+            Some(backend) => Ok(Self(Handle::new(backend))),
+            None => Err(WlrError::CallFailed("wlr_headless_backend_create".into())),
+        }
+    }
+
     pub fn from_ptr(ptr: *mut wlr_backend) -> Self {
         Self(Handle::new(ptr))
     }
@@ -24,6 +39,29 @@ impl Backend {
         &self.0
     }
 
+    /// Starts the backend, which begins emitting new outputs and input
+    /// devices.
+    pub fn start(&self) -> Result<(), WlrError> {
+        unsafe {
+            if !wlr_backend_start(self.0.try_as_ptr()?) {
+                return Err(WlrError::CallFailed("wlr_backend_start".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Adds an output to a headless backend, with a custom mode of the given
+    /// size. The backend must have been created with [`Backend::headless`].
+    pub fn add_headless_output(&self, width: u32, height: u32) -> Result<Output, WlrError> {
+        let ptr = unsafe { wlr_headless_add_output(self.0.try_as_ptr()?, width, height) };
+        if ptr.is_null() {
+            return Err(WlrError::CallFailed("wlr_headless_add_output".into()));
+        }
+
+        Ok(Output::from_ptr(ptr))
+    }
+
     pub fn on_new_output(&mut self, cb: impl Fn(Output) + 'static) {
         let signal = unsafe { &mut (*self.handle().as_ptr()).events.new_output };
         self.0.add_listener(signal, move |data| {
@@ -34,7 +72,9 @@ impl Backend {
 
 impl Drop for Backend {
     fn drop(&mut self) {
-        unsafe { wlr_backend_destroy(self.handle().as_ptr()) };
+        if let Ok(ptr) = self.0.try_as_ptr() {
+            unsafe { wlr_backend_destroy(ptr) };
+        }
     }
 }
 
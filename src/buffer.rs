@@ -0,0 +1,367 @@
+use std::{
+    io,
+    os::{
+        fd::{AsRawFd, FromRawFd, OwnedFd, RawFd},
+        raw::{c_char, c_void},
+    },
+    ptr,
+};
+
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{
+    wlr_buffer, wlr_buffer_drop, wlr_buffer_impl, wlr_buffer_init, wlr_buffer_lock,
+    wlr_buffer_unlock, wlr_shm_attributes,
+};
+
+use crate::{macros::*, Destroyable, Handle, WlrError};
+
+/// DRM fourcc code for 32-bit little-endian ARGB, the most widely supported
+/// format.
+pub const DRM_FORMAT_ARGB8888: u32 = fourcc(b"AR24");
+/// DRM fourcc code for 32-bit little-endian XRGB.
+pub const DRM_FORMAT_XRGB8888: u32 = fourcc(b"XR24");
+/// DRM fourcc code for 32-bit little-endian ABGR, which is RGBA in memory.
+pub const DRM_FORMAT_ABGR8888: u32 = fourcc(b"AB24");
+
+const fn fourcc(code: &[u8; 4]) -> u32 {
+    code[0] as u32 | (code[1] as u32) << 8 | (code[2] as u32) << 16 | (code[3] as u32) << 24
+}
+
+/// The size of a pixel in bytes, for the formats that rust-provided buffers
+/// support.
+fn bytes_per_pixel(format: u32) -> Option<usize> {
+    match format {
+        DRM_FORMAT_ARGB8888 | DRM_FORMAT_XRGB8888 | DRM_FORMAT_ABGR8888 => Some(4),
+        _ => None,
+    }
+}
+
+/// Checks that pixel data with the given layout covers the whole buffer,
+/// and returns the length of the data that's used.
+fn check_layout(
+    width: i32,
+    height: i32,
+    format: u32,
+    stride: usize,
+    len: usize,
+) -> Result<usize, WlrError> {
+    let bpp = bytes_per_pixel(format).ok_or(WlrError::UnsupportedFormat(format))?;
+    let invalid = || WlrError::InvalidBufferLayout {
+        width,
+        height,
+        stride,
+        len,
+    };
+
+    if width <= 0 || height <= 0 {
+        return Err(invalid());
+    }
+
+    let min_stride = (width as usize).checked_mul(bpp).ok_or_else(invalid)?;
+    let needed = stride.checked_mul(height as usize).ok_or_else(invalid)?;
+    if stride < min_stride || len < needed {
+        return Err(invalid());
+    }
+
+    Ok(needed)
+}
+
+/// A locked reference to a `struct wlr_buffer`. Buffers are reference
+/// counted by wlroots; the buffer stays alive at least as long as this
+/// wrapper.
+pub struct Buffer(Box<Handle<wlr_buffer>>);
+
+impl Buffer {
+    /// Takes a new lock on an existing buffer.
+    pub fn from_ptr(ptr: *mut wlr_buffer) -> Self {
+        unsafe { wlr_buffer_lock(ptr) };
+        Self(Handle::new(ptr))
+    }
+
+    /// Creates a buffer backed by pixel data in rust memory. `format` is a
+    /// DRM fourcc code, and `stride` is the length of a row in bytes. The
+    /// stride must fit a whole row, and the data must fit `height` rows.
+    pub fn from_pixels(
+        width: i32,
+        height: i32,
+        format: u32,
+        stride: usize,
+        data: Vec<u8>,
+    ) -> Result<Self, WlrError> {
+        check_layout(width, height, format, stride, data.len())?;
+
+        Ok(Self::from_storage(
+            width,
+            height,
+            format,
+            stride,
+            Box::new(data),
+        ))
+    }
+
+    /// Creates a buffer backed by a sealed memfd, initialized with a copy of
+    /// the given pixels. Unlike buffers backed by a `Vec`, these can be shared
+    /// with other processes and imported by backends as shared memory. The
+    /// layout is checked like in [`Buffer::from_pixels`].
+    pub fn from_pixels_memfd(
+        width: i32,
+        height: i32,
+        format: u32,
+        stride: usize,
+        data: &[u8],
+    ) -> Result<Self, WlrError> {
+        let len = check_layout(width, height, format, stride, data.len())?;
+        let mut storage = MemfdStorage::new(len)?;
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), storage.data_ptr(), len) };
+
+        Ok(Self::from_storage(
+            width,
+            height,
+            format,
+            stride,
+            Box::new(storage),
+        ))
+    }
+
+    /// Creates a buffer from any storage that can lend out a pointer to its
+    /// pixels.
+    pub(crate) fn from_storage(
+        width: i32,
+        height: i32,
+        format: u32,
+        stride: usize,
+        storage: Box<dyn PixelStorage>,
+    ) -> Self {
+        let buffer = Box::into_raw(Box::new(RustBuffer {
+            // SAFETY: wlr_buffer is plain old data, and is initialized by
+            // wlr_buffer_init below.
+            base: unsafe { std::mem::zeroed() },
+            format,
+            stride,
+            storage,
+        }));
+
+        unsafe {
+            let base = &mut (*buffer).base as *mut wlr_buffer;
+            wlr_buffer_init(base, &RUST_BUFFER_IMPL, width, height);
+
+            // Take our lock, and then give up the producer's reference, so
+            // that the buffer is freed as soon as the last lock is released.
+            let this = Self::from_ptr(base);
+            wlr_buffer_drop(base);
+            this
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_buffer> {
+        &self.0
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).width, (*p).height) }
+    }
+}
+
+impl Clone for Buffer {
+    fn clone(&self) -> Self {
+        Self::from_ptr(self.0.as_ptr())
+    }
+}
+
+impl Drop for Buffer {
+    fn drop(&mut self) {
+        if let Ok(ptr) = self.0.try_as_ptr() {
+            unsafe { wlr_buffer_unlock(ptr) }
+        }
+    }
+}
+
+impl Destroyable for wlr_buffer {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
+
+/// Memory that backs a rust-implemented buffer.
+pub(crate) trait PixelStorage {
+    fn data_ptr(&mut self) -> *mut u8;
+
+    /// The file descriptor backing the storage, if it's shareable.
+    fn shm_fd(&self) -> Option<RawFd> {
+        None
+    }
+}
+
+impl PixelStorage for Vec<u8> {
+    fn data_ptr(&mut self) -> *mut u8 {
+        self.as_mut_ptr()
+    }
+}
+
+struct MemfdStorage {
+    fd: OwnedFd,
+    ptr: *mut u8,
+    len: usize,
+}
+
+impl MemfdStorage {
+    fn new(len: usize) -> Result<Self, WlrError> {
+        let failed = || WlrError::Io(io::Error::last_os_error());
+
+        unsafe {
+            let fd = libc::memfd_create(
+                b"wlroots-rs-buffer\0".as_ptr() as *const c_char,
+                libc::MFD_CLOEXEC | libc::MFD_ALLOW_SEALING,
+            );
+            if fd < 0 {
+                return Err(failed());
+            }
+
+            let fd = OwnedFd::from_raw_fd(fd);
+            if libc::ftruncate(fd.as_raw_fd(), len as libc::off_t) < 0 {
+                return Err(failed());
+            }
+
+            // Clients of the buffer can't change its size out from under us.
+            let sealed = libc::fcntl(
+                fd.as_raw_fd(),
+                libc::F_ADD_SEALS,
+                libc::F_SEAL_SHRINK | libc::F_SEAL_GROW | libc::F_SEAL_SEAL,
+            );
+            if sealed < 0 {
+                return Err(failed());
+            }
+
+            let ptr = libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            );
+            if ptr == libc::MAP_FAILED {
+                return Err(failed());
+            }
+
+            Ok(Self {
+                fd,
+                ptr: ptr as *mut u8,
+                len,
+            })
+        }
+    }
+}
+
+impl PixelStorage for MemfdStorage {
+    fn data_ptr(&mut self) -> *mut u8 {
+        self.ptr
+    }
+
+    fn shm_fd(&self) -> Option<RawFd> {
+        Some(self.fd.as_raw_fd())
+    }
+}
+
+impl Drop for MemfdStorage {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.ptr as *mut c_void, self.len) };
+    }
+}
+
+#[repr(C)]
+struct RustBuffer {
+    base: wlr_buffer,
+    format: u32,
+    stride: usize,
+    storage: Box<dyn PixelStorage>,
+}
+
+static RUST_BUFFER_IMPL: wlr_buffer_impl = wlr_buffer_impl {
+    destroy: Some(rust_buffer_destroy),
+    get_dmabuf: None,
+    get_shm: Some(rust_buffer_get_shm),
+    begin_data_ptr_access: Some(rust_buffer_begin_data_ptr_access),
+    end_data_ptr_access: Some(rust_buffer_end_data_ptr_access),
+};
+
+unsafe extern "C" fn rust_buffer_destroy(buffer: *mut wlr_buffer) {
+    let buffer = container_of!(buffer, RustBuffer, base);
+    drop(Box::from_raw(buffer));
+}
+
+unsafe extern "C" fn rust_buffer_get_shm(
+    buffer: *mut wlr_buffer,
+    attribs: *mut wlr_shm_attributes,
+) -> bool {
+    let (width, height) = ((*buffer).width, (*buffer).height);
+    let buffer = &*container_of!(buffer, RustBuffer, base);
+    match buffer.storage.shm_fd() {
+        Some(fd) => {
+            *attribs = wlr_shm_attributes {
+                fd,
+                format: buffer.format,
+                width,
+                height,
+                stride: buffer.stride as i32,
+                offset: 0,
+            };
+            true
+        }
+        None => false,
+    }
+}
+
+unsafe extern "C" fn rust_buffer_begin_data_ptr_access(
+    buffer: *mut wlr_buffer,
+    _flags: u32,
+    data: *mut *mut c_void,
+    format: *mut u32,
+    stride: *mut usize,
+) -> bool {
+    let buffer = &mut *container_of!(buffer, RustBuffer, base);
+    *data = buffer.storage.data_ptr() as *mut c_void;
+    *format = buffer.format;
+    *stride = buffer.stride;
+    true
+}
+
+unsafe extern "C" fn rust_buffer_end_data_ptr_access(_buffer: *mut wlr_buffer) {
+    // Nothing to do; the memory stays mapped for the buffer's lifetime.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_accepts_padded_rows() {
+        assert_eq!(check_layout(2, 3, DRM_FORMAT_ARGB8888, 8, 24).unwrap(), 24);
+        assert_eq!(check_layout(2, 3, DRM_FORMAT_ARGB8888, 16, 50).unwrap(), 48);
+    }
+
+    #[test]
+    fn layout_rejects_short_stride_or_data() {
+        assert!(matches!(
+            check_layout(2, 3, DRM_FORMAT_ARGB8888, 7, 24),
+            Err(WlrError::InvalidBufferLayout { stride: 7, .. })
+        ));
+        assert!(matches!(
+            check_layout(2, 3, DRM_FORMAT_ARGB8888, 8, 23),
+            Err(WlrError::InvalidBufferLayout { len: 23, .. })
+        ));
+        assert!(matches!(
+            check_layout(0, 3, DRM_FORMAT_ARGB8888, 8, 24),
+            Err(WlrError::InvalidBufferLayout { .. })
+        ));
+    }
+
+    #[test]
+    fn layout_rejects_unknown_formats() {
+        assert!(matches!(
+            check_layout(2, 2, fourcc(b"NV12"), 8, 16),
+            Err(WlrError::UnsupportedFormat(_))
+        ));
+    }
+}
@@ -0,0 +1,108 @@
+use std::mem::MaybeUninit;
+
+use wlroots_sys::{
+    wlr_box, wlr_damage_ring, wlr_damage_ring_add, wlr_damage_ring_add_box,
+    wlr_damage_ring_add_whole, wlr_damage_ring_finish, wlr_damage_ring_get_buffer_damage,
+    wlr_damage_ring_init, wlr_damage_ring_rotate, wlr_damage_ring_set_bounds,
+};
+
+use crate::{Rect, Region};
+
+/// Tracks damage across the buffers of a swapchain, equivalent to
+/// `struct wlr_damage_ring`. Damage is accumulated in buffer-local
+/// coordinates; after each frame is committed, the ring is rotated, so that
+/// the damage for a buffer of a given age can be recovered.
+pub struct DamageRing(Box<wlr_damage_ring>);
+
+impl DamageRing {
+    pub fn new() -> Self {
+        // The ring is boxed so that it stays put while wlroots holds
+        // pointers into it.
+        let mut ring = Box::new(MaybeUninit::<wlr_damage_ring>::uninit());
+        unsafe {
+            wlr_damage_ring_init(ring.as_mut_ptr());
+            Self(Box::from_raw(Box::into_raw(ring) as *mut wlr_damage_ring))
+        }
+    }
+
+    /// Sets the size of the buffers. Changing the bounds damages the whole
+    /// ring.
+    pub fn set_bounds(&mut self, width: i32, height: i32) {
+        unsafe { wlr_damage_ring_set_bounds(self.as_mut_ptr(), width, height) }
+    }
+
+    pub fn bounds(&self) -> (i32, i32) {
+        (self.0.width, self.0.height)
+    }
+
+    /// Adds damage to the current frame. Returns false if the damage was
+    /// entirely out of bounds.
+    pub fn add(&mut self, damage: &Region) -> bool {
+        unsafe { wlr_damage_ring_add(self.as_mut_ptr(), damage.as_ptr()) }
+    }
+
+    /// Adds a damaged rectangle to the current frame. Returns false if the
+    /// rectangle was entirely out of bounds.
+    pub fn add_rect(&mut self, rect: Rect) -> bool {
+        let b: wlr_box = rect.into();
+        unsafe { wlr_damage_ring_add_box(self.as_mut_ptr(), &b) }
+    }
+
+    /// Damages the whole buffer, forcing a full repaint.
+    pub fn add_whole(&mut self) {
+        unsafe { wlr_damage_ring_add_whole(self.as_mut_ptr()) }
+    }
+
+    /// Returns true if any damage has been accumulated since the last
+    /// rotation.
+    pub fn has_damage(&self) -> bool {
+        !self.current().is_empty()
+    }
+
+    /// The damage accumulated since the last rotation.
+    pub fn current(&self) -> Region {
+        unsafe { Region::from_ptr(&self.0.current) }
+    }
+
+    /// Moves the current damage into the history. This should be called after
+    /// each successful commit.
+    pub fn rotate(&mut self) {
+        unsafe { wlr_damage_ring_rotate(self.as_mut_ptr()) }
+    }
+
+    /// Returns the region that must be repainted in a buffer of the given
+    /// age, as returned by the swapchain. An age of zero or one beyond the
+    /// ring's history yields the whole buffer.
+    pub fn buffer_damage(&self, buffer_age: i32) -> Region {
+        let mut damage = Region::new();
+        unsafe {
+            wlr_damage_ring_get_buffer_damage(
+                self.as_ptr() as *mut _,
+                buffer_age,
+                damage.as_mut_ptr(),
+            );
+        }
+
+        damage
+    }
+
+    pub fn as_ptr(&self) -> *const wlr_damage_ring {
+        &*self.0
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut wlr_damage_ring {
+        &mut *self.0
+    }
+}
+
+impl Default for DamageRing {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for DamageRing {
+    fn drop(&mut self) {
+        unsafe { wlr_damage_ring_finish(self.as_mut_ptr()) }
+    }
+}
@@ -0,0 +1,303 @@
+use std::{
+    cell::Cell,
+    os::raw::{c_int, c_void},
+    rc::Rc,
+    time::Duration,
+};
+
+use wayland_sys::server::{
+    wl_display_get_event_loop, wl_event_loop_add_timer, wl_event_source, wl_event_source_remove,
+    wl_event_source_timer_update,
+};
+
+use crate::{monotonic_now, Output, OutputPresentEvent, WlrError};
+
+/// Presentation statistics for an output, derived from present events.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameStats {
+    /// When the last frame was presented, on the monotonic clock.
+    pub last_present: Option<Duration>,
+    /// The refresh interval reported with the last presented frame.
+    pub refresh: Option<Duration>,
+    /// The number of frames that were displayed.
+    pub presented: u64,
+    /// The number of frames that were submitted but discarded.
+    pub discarded: u64,
+    /// The number of vblanks that passed between a frame event and the
+    /// presentation of the frame rendered for it. Vblanks that pass while the
+    /// output is idle aren't counted.
+    pub missed: u64,
+    last_seq: Option<u64>,
+    frame_seq: Option<u64>,
+}
+
+impl FrameStats {
+    fn record(&mut self, event: &OutputPresentEvent) {
+        if !event.presented {
+            self.discarded += 1;
+            self.frame_seq = None;
+            return;
+        }
+
+        self.presented += 1;
+        if event.when.is_some() {
+            self.last_present = event.when;
+        }
+
+        if event.refresh > 0 {
+            self.refresh = Some(Duration::from_nanos(event.refresh as u64));
+        }
+
+        if event.seq != 0 {
+            if let Some(frame_seq) = self.frame_seq.take() {
+                self.missed += event.seq.saturating_sub(frame_seq).saturating_sub(1);
+            }
+            self.last_seq = Some(event.seq);
+        }
+    }
+
+    /// Records a frame event, which starts a new frame. Only the vblanks that
+    /// pass between the last frame event and the next present count as
+    /// missed, so idle periods are ignored.
+    fn record_frame(&mut self, now: Duration) {
+        self.frame_seq = self.vblank_at(now);
+    }
+
+    /// Estimates the sequence number of the last vblank before `time`.
+    fn vblank_at(&self, time: Duration) -> Option<u64> {
+        let last_seq = self.last_seq?;
+        let refresh = self.refresh?.as_nanos();
+        let elapsed = time.saturating_sub(self.last_present?).as_nanos();
+        Some(last_seq + (elapsed / refresh) as u64)
+    }
+
+    /// Estimates when the next vblank will happen, if the output reports its
+    /// refresh rate.
+    pub fn next_present(&self) -> Option<Duration> {
+        Some(self.last_present? + self.refresh?)
+    }
+}
+
+/// Calls a render callback on each frame event of an output, optionally
+/// delaying it so that rendering finishes just before the next vblank. This
+/// reduces latency, at the risk of missing the vblank if rendering takes
+/// longer than the configured budget.
+///
+/// Dropping the scheduler stops rendering.
+pub struct FrameScheduler {
+    state: Rc<SchedulerState>,
+    // The frame and present listeners are registered here rather than on the
+    // caller's Output, so they go away with the scheduler. The state's own
+    // wrapper can't take them, since adding listeners needs `&mut`.
+    output: Output,
+}
+
+struct SchedulerState {
+    output: Output,
+    timer: Cell<*mut wl_event_source>,
+    delayed: Cell<bool>,
+    max_render_time: Cell<Option<Duration>>,
+    stats: Cell<FrameStats>,
+    render: Box<dyn Fn()>,
+}
+
+impl FrameScheduler {
+    pub fn attach(output: &Output, render: impl Fn() + 'static) -> Result<Self, WlrError> {
+        let ptr = output.handle().try_as_ptr()?;
+        let mut output = Output::from_ptr(ptr);
+        let state = Rc::new(SchedulerState {
+            output: Output::from_ptr(ptr),
+            timer: Cell::new(std::ptr::null_mut()),
+            delayed: Cell::new(false),
+            max_render_time: Cell::new(None),
+            stats: Cell::new(FrameStats::default()),
+            render: Box::new(render),
+        });
+
+        unsafe {
+            let event_loop = wl_display_get_event_loop((*ptr).display);
+            let timer = wl_event_loop_add_timer(
+                event_loop,
+                render_timer_callback,
+                Rc::as_ptr(&state) as *mut c_void,
+            );
+            if timer.is_null() {
+                return Err(WlrError::CallFailed("wl_event_loop_add_timer".into()));
+            }
+
+            state.timer.set(timer);
+        }
+
+        let frame_state = Rc::downgrade(&state);
+        output.on_frame(move || {
+            if let Some(state) = frame_state.upgrade() {
+                state.on_frame();
+            }
+        });
+
+        let present_state = Rc::downgrade(&state);
+        output.on_present(move |event| {
+            if let Some(state) = present_state.upgrade() {
+                let mut stats = state.stats.get();
+                stats.record(&event);
+                state.stats.set(stats);
+            }
+        });
+
+        Ok(Self { state, output })
+    }
+
+    /// Sets how long before the next vblank rendering should start. None (the
+    /// default) renders as soon as the frame event arrives.
+    pub fn set_max_render_time(&self, max_render_time: Option<Duration>) {
+        self.state.max_render_time.set(max_render_time);
+    }
+
+    pub fn max_render_time(&self) -> Option<Duration> {
+        self.state.max_render_time.get()
+    }
+
+    pub fn stats(&self) -> FrameStats {
+        self.state.stats.get()
+    }
+
+    /// Asks for a frame event on the output.
+    pub fn schedule_frame(&self) {
+        self.state.output.schedule_frame();
+    }
+
+    /// Returns true if a frame has been submitted and not yet presented, or
+    /// rendering is waiting on the delay timer.
+    pub fn frame_pending(&self) -> bool {
+        self.state.output.frame_pending() || self.state.delayed()
+    }
+}
+
+impl SchedulerState {
+    fn on_frame(&self) {
+        let mut stats = self.stats.get();
+        stats.record_frame(monotonic_now());
+        self.stats.set(stats);
+
+        let delay = self.render_delay();
+        if delay < Duration::from_millis(1) {
+            (self.render)();
+            return;
+        }
+
+        unsafe { wl_event_source_timer_update(self.timer.get(), delay.as_millis() as c_int) };
+        self.delayed.set(true);
+    }
+
+    fn render_delay(&self) -> Duration {
+        let max_render_time = match self.max_render_time.get() {
+            Some(v) => v,
+            None => return Duration::ZERO,
+        };
+
+        match self.stats.get().next_present() {
+            Some(next) => next
+                .saturating_sub(monotonic_now())
+                .saturating_sub(max_render_time),
+            None => Duration::ZERO,
+        }
+    }
+
+    fn delayed(&self) -> bool {
+        self.delayed.get()
+    }
+}
+
+impl Drop for FrameScheduler {
+    fn drop(&mut self) {
+        let timer = self.state.timer.replace(std::ptr::null_mut());
+        if !timer.is_null() {
+            unsafe { wl_event_source_remove(timer) };
+        }
+    }
+}
+
+unsafe extern "C" fn render_timer_callback(data: *mut c_void) -> c_int {
+    let state = &*(data as *const SchedulerState);
+    state.delayed.set(false);
+    (state.render)();
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PresentFlags;
+
+    const REFRESH: Duration = Duration::from_millis(16);
+
+    fn present(seq: u64) -> OutputPresentEvent {
+        OutputPresentEvent {
+            commit_seq: 0,
+            presented: true,
+            when: Some(REFRESH * seq as u32),
+            seq,
+            refresh: REFRESH.as_nanos() as i32,
+            flags: PresentFlags::default(),
+        }
+    }
+
+    #[test]
+    fn counts_presented_and_discarded() {
+        let mut stats = FrameStats::default();
+        stats.record(&present(1));
+        stats.record(&OutputPresentEvent {
+            presented: false,
+            ..present(2)
+        });
+
+        assert_eq!(stats.presented, 1);
+        assert_eq!(stats.discarded, 1);
+        assert_eq!(stats.last_present, Some(REFRESH));
+        assert_eq!(stats.refresh, Some(REFRESH));
+        assert_eq!(stats.next_present(), Some(REFRESH * 2));
+    }
+
+    #[test]
+    fn continuous_frames_miss_nothing() {
+        let mut stats = FrameStats::default();
+        stats.record(&present(1));
+        for seq in 2..10 {
+            stats.record_frame(REFRESH * (seq - 1) as u32 + Duration::from_millis(1));
+            stats.record(&present(seq));
+        }
+
+        assert_eq!(stats.presented, 9);
+        assert_eq!(stats.missed, 0);
+    }
+
+    #[test]
+    fn late_frames_are_missed() {
+        let mut stats = FrameStats::default();
+        stats.record(&present(1));
+        stats.record_frame(REFRESH + Duration::from_millis(1));
+        stats.record(&present(4));
+
+        assert_eq!(stats.missed, 2);
+    }
+
+    #[test]
+    fn idle_periods_are_not_missed() {
+        let mut stats = FrameStats::default();
+        stats.record(&present(1));
+        // Nothing is rendered until vblank 100.
+        stats.record_frame(REFRESH * 100 + Duration::from_millis(1));
+        stats.record(&present(101));
+
+        assert_eq!(stats.missed, 0);
+    }
+
+    #[test]
+    fn presents_without_frame_events_are_not_missed() {
+        let mut stats = FrameStats::default();
+        stats.record(&present(1));
+        stats.record(&present(50));
+
+        assert_eq!(stats.missed, 0);
+    }
+}
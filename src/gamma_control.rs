@@ -0,0 +1,88 @@
+use wayland_server::DisplayHandle;
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{
+    wlr_gamma_control_manager_v1, wlr_gamma_control_manager_v1_create,
+    wlr_gamma_control_manager_v1_get_control, wlr_gamma_control_manager_v1_set_gamma_event,
+    wlr_gamma_control_v1_apply, wlr_gamma_control_v1_send_failed_and_destroy,
+};
+
+use crate::{Destroyable, Handle, Output, OutputState, WlrError};
+
+/// Implements the wlr-gamma-control-unstable-v1 protocol, used by tools like
+/// gammastep and wlsunset to adjust color temperature.
+pub struct GammaControlManager(Box<Handle<wlr_gamma_control_manager_v1>>);
+
+impl GammaControlManager {
+    pub fn new(display: &DisplayHandle) -> Result<Self, WlrError> {
+        let display = display.backend_handle().display_ptr();
+        let ptr = unsafe { wlr_gamma_control_manager_v1_create(display).as_mut() };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed(
+                "wlr_gamma_control_manager_v1_create".into(),
+            )),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_gamma_control_manager_v1> {
+        &self.0
+    }
+
+    /// Called when a client sets (or resets) the gamma ramps for an output.
+    /// The compositor should call [`GammaControlManager::apply`] with a
+    /// pending state for the output, and commit it.
+    pub fn on_set_gamma(&mut self, cb: impl Fn(GammaControlSetGammaEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.set_gamma };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_gamma_control_manager_v1_set_gamma_event) };
+            (cb)(GammaControlSetGammaEvent {
+                output: Output::from_ptr(event.output),
+            });
+        });
+    }
+
+    /// Applies the gamma ramps requested by the client controlling the
+    /// output to a pending state. If no client is controlling the output, the
+    /// state resets the gamma ramps instead.
+    pub fn apply(&self, output: &Output, state: &mut OutputState) -> Result<(), WlrError> {
+        let manager = self.0.try_as_ptr()?;
+        let output = output.handle().try_as_ptr()?;
+
+        unsafe {
+            let control = wlr_gamma_control_manager_v1_get_control(manager, output);
+            if !wlr_gamma_control_v1_apply(control, state.as_mut_ptr()) {
+                return Err(WlrError::CallFailed("wlr_gamma_control_v1_apply".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Notifies the client controlling the output that its gamma ramps could
+    /// not be applied, for example because the commit failed. This destroys
+    /// the client's control object.
+    pub fn send_failed(&self, output: &Output) -> Result<(), WlrError> {
+        let manager = self.0.try_as_ptr()?;
+        let output = output.handle().try_as_ptr()?;
+
+        unsafe {
+            let control = wlr_gamma_control_manager_v1_get_control(manager, output);
+            if !control.is_null() {
+                wlr_gamma_control_v1_send_failed_and_destroy(control);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Destroyable for wlr_gamma_control_manager_v1 {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
+
+pub struct GammaControlSetGammaEvent {
+    pub output: Output,
+}
@@ -0,0 +1,247 @@
+use std::{mem::MaybeUninit, os::raw::c_int};
+
+use wlroots_sys::{
+    pixman_box32_t, pixman_region32_clear, pixman_region32_fini, pixman_region32_init,
+    pixman_region32_init_rect, pixman_region32_not_empty, pixman_region32_rectangles,
+    pixman_region32_t, pixman_region32_union, pixman_region32_union_rect, wlr_box, wlr_fbox,
+};
+
+/// An integer rectangle, equivalent to `struct wlr_box`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width <= 0 || self.height <= 0
+    }
+
+    /// Returns true if the rectangles share any area.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.x < other.x + other.width
+            && other.x < self.x + self.width
+            && self.y < other.y + other.height
+            && other.y < self.y + self.height
+    }
+}
+
+impl From<wlr_box> for Rect {
+    fn from(b: wlr_box) -> Self {
+        Self::new(b.x, b.y, b.width, b.height)
+    }
+}
+
+impl From<Rect> for wlr_box {
+    fn from(r: Rect) -> Self {
+        wlr_box {
+            x: r.x,
+            y: r.y,
+            width: r.width,
+            height: r.height,
+        }
+    }
+}
+
+impl From<&pixman_box32_t> for Rect {
+    fn from(b: &pixman_box32_t) -> Self {
+        Self::new(b.x1, b.y1, b.x2 - b.x1, b.y2 - b.y1)
+    }
+}
+
+/// A floating-point rectangle, equivalent to `struct wlr_fbox`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FRect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl FRect {
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+impl From<FRect> for wlr_fbox {
+    fn from(r: FRect) -> Self {
+        wlr_fbox {
+            x: r.x,
+            y: r.y,
+            width: r.width,
+            height: r.height,
+        }
+    }
+}
+
+/// An owned pixman region, used for damage tracking.
+pub struct Region(pixman_region32_t);
+
+impl Region {
+    pub fn new() -> Self {
+        let mut region = MaybeUninit::<pixman_region32_t>::uninit();
+        unsafe {
+            pixman_region32_init(region.as_mut_ptr());
+            Self(region.assume_init())
+        }
+    }
+
+    pub fn from_rect(rect: Rect) -> Self {
+        let mut region = MaybeUninit::<pixman_region32_t>::uninit();
+        unsafe {
+            pixman_region32_init_rect(
+                region.as_mut_ptr(),
+                rect.x,
+                rect.y,
+                rect.width.max(0) as u32,
+                rect.height.max(0) as u32,
+            );
+            Self(region.assume_init())
+        }
+    }
+
+    /// Copies the rectangles of a region owned by wlroots.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must point to an initialized region.
+    pub unsafe fn from_ptr(ptr: *const pixman_region32_t) -> Self {
+        let mut region = Self::new();
+        pixman_region32_union(region.as_mut_ptr(), region.as_mut_ptr(), ptr as *mut _);
+        region
+    }
+
+    pub fn add_rect(&mut self, rect: Rect) {
+        if rect.is_empty() {
+            return;
+        }
+
+        unsafe {
+            pixman_region32_union_rect(
+                self.as_mut_ptr(),
+                self.as_mut_ptr(),
+                rect.x,
+                rect.y,
+                rect.width as u32,
+                rect.height as u32,
+            );
+        }
+    }
+
+    pub fn add_region(&mut self, other: &Region) {
+        unsafe {
+            pixman_region32_union(
+                self.as_mut_ptr(),
+                self.as_mut_ptr(),
+                other.as_ptr() as *mut _,
+            );
+        }
+    }
+
+    pub fn clear(&mut self) {
+        unsafe { pixman_region32_clear(self.as_mut_ptr()) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        unsafe { pixman_region32_not_empty(self.as_ptr() as *mut _) == 0 }
+    }
+
+    /// Returns the rectangles that make up the region.
+    pub fn rects(&self) -> Vec<Rect> {
+        let mut n: c_int = 0;
+        unsafe {
+            let boxes = pixman_region32_rectangles(self.as_ptr() as *mut _, &mut n);
+            if boxes.is_null() || n <= 0 {
+                return Vec::new();
+            }
+
+            std::slice::from_raw_parts(boxes, n as usize)
+                .iter()
+                .map(Rect::from)
+                .collect()
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const pixman_region32_t {
+        &self.0
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut pixman_region32_t {
+        &mut self.0
+    }
+}
+
+impl Default for Region {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Region {
+    fn clone(&self) -> Self {
+        unsafe { Self::from_ptr(self.as_ptr()) }
+    }
+}
+
+impl std::fmt::Debug for Region {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Region").field(&self.rects()).finish()
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        unsafe { pixman_region32_fini(self.as_mut_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_rects_intersect() {
+        let a = Rect::new(0, 0, 10, 10);
+        assert!(a.intersects(&Rect::new(5, 5, 10, 10)));
+        assert!(a.intersects(&Rect::new(-5, -5, 6, 6)));
+        assert!(a.intersects(&Rect::new(2, 2, 2, 2)));
+        assert!(Rect::new(2, 2, 2, 2).intersects(&a));
+    }
+
+    #[test]
+    fn touching_rects_dont_intersect() {
+        let a = Rect::new(0, 0, 10, 10);
+        assert!(!a.intersects(&Rect::new(10, 0, 10, 10)));
+        assert!(!a.intersects(&Rect::new(0, 10, 10, 10)));
+        assert!(!a.intersects(&Rect::new(-10, -10, 10, 10)));
+        assert!(!a.intersects(&Rect::new(20, 20, 5, 5)));
+    }
+
+    #[test]
+    fn empty_rects_never_intersect() {
+        let a = Rect::new(0, 0, 10, 10);
+        assert!(!a.intersects(&Rect::new(5, 5, 0, 10)));
+        assert!(!a.intersects(&Rect::new(5, 5, 10, -1)));
+        assert!(!Rect::default().intersects(&Rect::default()));
+    }
+}
@@ -0,0 +1,100 @@
+use wayland_server::{Display, DisplayHandle};
+use wlroots_sys::{wlr_allocator_destroy, wlr_backend_destroy, wlr_renderer_destroy};
+
+use crate::{Allocator, Backend, Image, Output, OutputState, Renderer, Scene, WlrError};
+
+/// Renders scenes offscreen, on a headless backend with the software
+/// renderer, for pixel-exact rendering tests that don't need a GPU. Compare
+/// the results with [`Image::compare`], or against golden PNGs with
+/// `Image::check_golden`.
+pub struct HeadlessHarness {
+    backend: Backend,
+    renderer: Renderer,
+    allocator: Allocator,
+    output: Output,
+    // The backend needs a display for its event loop, so it's dropped last.
+    display: Display<()>,
+}
+
+impl HeadlessHarness {
+    /// Creates a headless backend with a single enabled output of the given
+    /// size.
+    pub fn new(width: u32, height: u32) -> Result<Self, WlrError> {
+        let display: Display<()> =
+            Display::new().map_err(|_| WlrError::CallFailed("wl_display_create".into()))?;
+
+        let backend = Backend::headless(&display.handle())?;
+        let renderer = Renderer::pixman()?;
+        let allocator = Allocator::autocreate(&backend, &renderer)?;
+
+        let mut output = backend.add_headless_output(width, height)?;
+        output.init_render(&allocator, &renderer)?;
+        backend.start()?;
+
+        let mut state = OutputState::new();
+        state.set_enabled(true);
+        state.set_custom_mode(width as i32, height as i32, 0);
+        output.commit_state(&state)?;
+
+        Ok(Self {
+            backend,
+            renderer,
+            allocator,
+            output,
+            display,
+        })
+    }
+
+    /// The display that the backend runs on, for creating globals.
+    pub fn display_handle(&self) -> DisplayHandle {
+        self.display.handle()
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn renderer(&self) -> &Renderer {
+        &self.renderer
+    }
+
+    /// Renders the whole scene from scratch, and reads back the result in
+    /// [`DRM_FORMAT_ABGR8888`](crate::DRM_FORMAT_ABGR8888). The output shows
+    /// the part of the scene at its scene output's position, which is the
+    /// origin unless it's been moved.
+    pub fn render(&self, scene: &Scene) -> Result<Image, WlrError> {
+        let scene_output = match scene.get_output(&self.output) {
+            Some(scene_output) => scene_output,
+            None => scene.create_output(&self.output)?,
+        };
+
+        // Repaint everything, so that the result doesn't depend on what was
+        // rendered before.
+        scene_output.damage_whole();
+
+        let mut state = OutputState::new();
+        scene_output.build_state(&mut state)?;
+        match state.buffer() {
+            Some(buffer) => self.renderer.read_pixels(&buffer),
+            None => Err(WlrError::CallFailed("wlr_scene_output_build_state".into())),
+        }
+    }
+}
+
+impl Drop for HeadlessHarness {
+    fn drop(&mut self) {
+        // Outputs hold buffers from the allocator, which in turn may hold
+        // on to the renderer, so tear down in that order.
+        unsafe {
+            if let Ok(backend) = self.backend.handle().try_as_ptr() {
+                wlr_backend_destroy(backend);
+            }
+            if let Ok(allocator) = self.allocator.handle().try_as_ptr() {
+                wlr_allocator_destroy(allocator);
+            }
+            if let Ok(renderer) = self.renderer.handle().try_as_ptr() {
+                wlr_renderer_destroy(renderer);
+            }
+        }
+    }
+}
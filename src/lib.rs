@@ -1,26 +1,78 @@
 mod allocator;
 mod backend;
+mod buffer;
 mod compositor;
+mod damage_ring;
 mod data_device_manager;
+mod frame_scheduler;
+mod gamma_control;
+mod geometry;
+mod headless;
+mod linux_dmabuf;
 mod output;
+mod output_cursor;
 mod output_layout;
+mod output_management;
+mod output_power;
+mod output_profile;
+mod output_state;
+mod presentation;
+mod render_loop;
 mod renderer;
 mod scene;
+mod scene_buffer;
+mod scene_dump;
+mod scene_node;
+mod scene_output;
+mod scene_rect;
+mod screenshot;
 mod subcompositor;
+mod surface;
+mod xdg_output;
 mod xdg_shell;
 
 pub use allocator::Allocator;
 pub use backend::Backend;
+pub use buffer::{Buffer, DRM_FORMAT_ABGR8888, DRM_FORMAT_ARGB8888, DRM_FORMAT_XRGB8888};
 pub use compositor::Compositor;
+pub use damage_ring::DamageRing;
 pub use data_device_manager::DataDeviceManager;
-pub use output::{Output, OutputMode};
-pub use output_layout::OutputLayout;
-pub use renderer::Renderer;
+pub use frame_scheduler::{FrameScheduler, FrameStats};
+pub use gamma_control::{GammaControlManager, GammaControlSetGammaEvent};
+pub use geometry::{FRect, Rect, Region};
+pub use headless::HeadlessHarness;
+pub use linux_dmabuf::LinuxDmabuf;
+pub use output::{
+    AspectRatio, ModeInfo, Output, OutputBindEvent, OutputCommitEvent, OutputDamageEvent,
+    OutputPrecommitEvent, OutputPresentEvent, OutputRequestStateEvent, PresentFlags,
+};
+pub use output_cursor::OutputCursor;
+pub use output_layout::{Direction, LayoutOutput, OutputLayout};
+pub use output_management::{
+    ConfigurationResult, OutputConfiguration, OutputConfigurationHead, OutputManager,
+};
+pub use output_power::{OutputPowerManager, OutputPowerMode, OutputPowerSetModeEvent};
+pub use output_profile::{
+    ModeSpec, OutputIdentity, OutputMatcher, OutputProfile, OutputProfileEntry, OutputProfiles,
+    ProfileTransform,
+};
+pub use output_state::{OutputState, OutputStateFields};
+pub use presentation::Presentation;
+pub use render_loop::OutputRenderLoop;
+pub use renderer::{RenderPass, Renderer};
 pub use scene::Scene;
+pub use scene_buffer::{FilterMode, SceneBuffer};
+pub use scene_dump::SceneDump;
+pub use scene_node::{SceneHit, SceneNode, SceneNodeType, SceneTree};
+pub use scene_output::SceneOutput;
+pub use scene_rect::SceneRect;
+pub use screenshot::{Image, ImageDiff, OutputCapture};
 pub use subcompositor::Subcompositor;
-pub use xdg_shell::XdgShell;
+pub use surface::Surface;
+pub use xdg_output::XdgOutputManager;
+pub use xdg_shell::{XdgShell, XdgSurface, XdgSurfaceRole};
 
-use std::{any::type_name, os::raw::c_void};
+use std::{any::type_name, os::raw::c_void, time::Duration};
 use thin_trait_object::*;
 
 use wayland_sys::server::{
@@ -50,6 +102,8 @@ pub trait Destroyable {
 /// usage, this is unlikely to happen.
 pub struct Handle<T: Destroyable> {
     ptr: Option<*mut T>,
+    destroy_listener: Option<*mut wl_listener>,
+    destroy_callbacks: Vec<Box<dyn Fn()>>,
     listeners: Vec<*mut wl_listener>,
 }
 
@@ -58,6 +112,8 @@ impl<T: Destroyable> Handle<T> {
         // We return a box so that the handle is findable by callbacks.
         let mut handle = Box::new(Self {
             ptr: Some(ptr),
+            destroy_listener: None,
+            destroy_callbacks: Vec::new(),
             listeners: Vec::new(),
         });
 
@@ -70,6 +126,7 @@ impl<T: Destroyable> Handle<T> {
             wl_signal_add(signal, listener);
         }
 
+        handle.destroy_listener = Some(listener);
         handle
     }
 
@@ -98,8 +155,20 @@ impl<T: Destroyable> Handle<T> {
         }
     }
 
+    /// Registers a callback to run when the object is destroyed, while it's
+    /// still valid. Unlike listeners added with add_listener, these run
+    /// before the handle detaches from the object's signals.
+    fn add_destroy_callback(&mut self, callback: impl Fn() + 'static) {
+        self.destroy_callbacks.push(Box::new(callback));
+    }
+
     fn on_destroy(&mut self) {
+        for callback in std::mem::take(&mut self.destroy_callbacks) {
+            (callback)();
+        }
+
         self.ptr = None;
+        self.destroy_listener = None;
         self.cleanup_listeners();
     }
 
@@ -124,8 +193,18 @@ impl<T: Destroyable> Drop for Handle<T> {
     fn drop(&mut self) {
         // Dropping the handle doesn't necessarily mean the underlying wlr_foo
         // is freed. Most objects are managed by wlroots itself. However, we
-        // should remove and drop any rust listeners.
+        // should remove and drop any rust listeners, including the destroy
+        // listener, which points back at this handle.
         self.cleanup_listeners();
+
+        if let Some(listener) = self.destroy_listener.take() {
+            // SAFETY: the listener was created by us in new, and is still
+            // attached to the destroy signal.
+            unsafe {
+                wl_list_remove(&mut (*listener).link);
+                rust_listener_destroy(listener);
+            }
+        }
     }
 }
 
@@ -198,6 +277,63 @@ pub enum WlrError {
     ObjectDestroyed(String),
     #[error("call to {0} failed")]
     CallFailed(String),
+    #[error("unsupported pixel format {0:#x}")]
+    UnsupportedFormat(u32),
+    #[error("invalid {width}x{height} buffer with stride {stride} and {len} bytes")]
+    InvalidBufferLayout {
+        width: i32,
+        height: i32,
+        stride: usize,
+        len: usize,
+    },
+    #[error("no buffer has been captured")]
+    NothingCaptured,
+    #[error("gamma ramps have different lengths (red {red}, green {green}, blue {blue})")]
+    GammaSizeMismatch {
+        red: usize,
+        green: usize,
+        blue: usize,
+    },
+    #[error("the scene nodes aren't distinct siblings")]
+    NotSiblings,
+    #[error("a scene node can't be moved into itself or its descendants")]
+    ReparentCycle,
+    #[error("profile {0} doesn't match the connected outputs")]
+    ProfileMismatch(String),
+    #[error("the scene output shows a different output")]
+    SceneOutputMismatch,
+    #[error("the {0} has already been set")]
+    AlreadySet(String),
+    #[error("surface nodes take their input region from the surface")]
+    SurfaceNode,
+    #[cfg(feature = "png")]
+    #[error("PNG encoding failed: {0}")]
+    Png(#[from] png::EncodingError),
+    #[cfg(feature = "png")]
+    #[error("PNG decoding failed: {0}")]
+    PngDecode(#[from] png::DecodingError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("images have different formats ({actual:#x}, expected {expected:#x})")]
+    FormatMismatch { expected: u32, actual: u32 },
+    #[cfg(feature = "png")]
+    #[error("{differing} pixels differ from {golden}; see {diff}")]
+    ImageMismatch {
+        golden: String,
+        differing: usize,
+        diff: String,
+    },
+}
+
+/// The current time on the monotonic clock, which wlroots uses for frame and
+/// presentation timestamps.
+pub(crate) fn monotonic_now() -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
 }
 
 pub(crate) mod macros {
@@ -0,0 +1,44 @@
+use wayland_server::DisplayHandle;
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{wlr_linux_dmabuf_v1, wlr_linux_dmabuf_v1_create_with_renderer};
+
+use crate::{Destroyable, Handle, Renderer, WlrError};
+
+/// Implements the linux-dmabuf-unstable-v1 protocol, which lets clients
+/// share GPU buffers with the compositor. The formats advertised by default
+/// are those the renderer can import; a scene given the manager with
+/// [`Scene::set_linux_dmabuf_v1`](crate::Scene::set_linux_dmabuf_v1) also
+/// sends per-surface feedback, so that clients can pick formats suitable for
+/// direct scanout.
+pub struct LinuxDmabuf(Box<Handle<wlr_linux_dmabuf_v1>>);
+
+impl LinuxDmabuf {
+    pub fn new(
+        display: &DisplayHandle,
+        version: u32,
+        renderer: &Renderer,
+    ) -> Result<Self, WlrError> {
+        let display = display.backend_handle().display_ptr();
+        let renderer = renderer.handle().try_as_ptr()?;
+        let ptr = unsafe {
+            wlr_linux_dmabuf_v1_create_with_renderer(display, version, renderer).as_mut()
+        };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed(
+                "wlr_linux_dmabuf_v1_create_with_renderer".into(),
+            )),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_linux_dmabuf_v1> {
+        &self.0
+    }
+}
+
+impl Destroyable for wlr_linux_dmabuf_v1 {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
@@ -1,10 +1,40 @@
+use std::{
+    cmp::Ordering,
+    ffi::{CStr, CString},
+    fmt,
+    os::raw::c_char,
+    time::Duration,
+};
+
+use libc::timespec;
+use wayland_server::{
+    backend::ObjectId,
+    protocol::wl_output::{Subpixel, Transform, WlOutput},
+    Resource,
+};
 use wayland_sys::{common::wl_list, server::wl_signal};
 use wlroots_sys::{
-    wlr_output, wlr_output_commit, wlr_output_enable, wlr_output_init_render, wlr_output_mode,
-    wlr_output_preferred_mode, wlr_output_set_mode,
+    wlr_output, wlr_output_commit, wlr_output_commit_state, wlr_output_enable,
+    wlr_output_event_bind, wlr_output_event_commit, wlr_output_event_damage,
+    wlr_output_event_precommit, wlr_output_event_present, wlr_output_event_request_state,
+    wlr_output_get_gamma_size, wlr_output_init_render, wlr_output_lock_software_cursors,
+    wlr_output_mode, wlr_output_mode_aspect_ratio,
+    wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_16_9,
+    wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_256_135,
+    wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_4_3,
+    wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_64_27, wlr_output_preferred_mode,
+    wlr_output_present_flag_WLR_OUTPUT_PRESENT_HW_CLOCK,
+    wlr_output_present_flag_WLR_OUTPUT_PRESENT_HW_COMPLETION,
+    wlr_output_present_flag_WLR_OUTPUT_PRESENT_VSYNC,
+    wlr_output_present_flag_WLR_OUTPUT_PRESENT_ZERO_COPY, wlr_output_schedule_frame,
+    wlr_output_set_custom_mode, wlr_output_set_description, wlr_output_set_mode,
+    wlr_output_test_state,
 };
 
-use crate::{macros::*, Allocator, ChildHandle, Destroyable, Handle, Renderer, WlrError};
+use crate::{
+    macros::*, Allocator, Buffer, Destroyable, Handle, OutputState, OutputStateFields, Region,
+    Renderer, WlrError,
+};
 
 pub struct Output(Box<Handle<wlr_output>>);
 
@@ -17,31 +47,127 @@ impl Output {
         &self.0
     }
 
-    pub fn modes(&self) -> impl Iterator<Item = OutputMode> {
+    /// The name of the output, as assigned by the backend (for example,
+    /// "DP-1" or "HEADLESS-1").
+    pub fn name(&self) -> String {
+        unsafe { string_from_ptr((*self.0.as_ptr()).name).unwrap_or_default() }
+    }
+
+    /// A human-readable description of the output, if one is set.
+    pub fn description(&self) -> Option<String> {
+        unsafe { string_from_ptr((*self.0.as_ptr()).description) }
+    }
+
+    /// Sets the description of the output, which is advertised to clients.
+    pub fn set_description(&self, description: &str) {
+        // Interior nul bytes can't be represented; truncate at the first one.
+        let description = description.split('\0').next().unwrap_or_default();
+        let description = CString::new(description).unwrap();
+        unsafe { wlr_output_set_description(self.0.as_ptr(), description.as_ptr()) }
+    }
+
+    pub fn make(&self) -> Option<String> {
+        unsafe { string_from_ptr((*self.0.as_ptr()).make) }
+    }
+
+    pub fn model(&self) -> Option<String> {
+        unsafe { string_from_ptr((*self.0.as_ptr()).model) }
+    }
+
+    pub fn serial(&self) -> Option<String> {
+        unsafe { string_from_ptr((*self.0.as_ptr()).serial) }
+    }
+
+    /// The physical size of the output in millimeters, or (0, 0) if unknown.
+    pub fn physical_size(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).phys_width, (*p).phys_height) }
+    }
+
+    pub fn subpixel(&self) -> Subpixel {
+        let subpixel = unsafe { (*self.0.as_ptr()).subpixel };
+        Subpixel::try_from(subpixel as u32).unwrap_or(Subpixel::Unknown)
+    }
+
+    pub fn transform(&self) -> Transform {
+        let transform = unsafe { (*self.0.as_ptr()).transform };
+        Transform::try_from(transform as u32).unwrap_or(Transform::Normal)
+    }
+
+    pub fn scale(&self) -> f32 {
+        unsafe { (*self.0.as_ptr()).scale }
+    }
+
+    pub fn enabled(&self) -> bool {
+        unsafe { (*self.0.as_ptr()).enabled }
+    }
+
+    /// The number of entries in each of the output's gamma ramps, or zero if
+    /// gamma can't be adjusted.
+    pub fn gamma_size(&self) -> usize {
+        unsafe { wlr_output_get_gamma_size(self.0.as_ptr()) }
+    }
+
+    pub fn modes(&self) -> impl Iterator<Item = ModeInfo> + '_ {
+        self.mode_ptrs().map(ModeInfo::from_ptr)
+    }
+
+    fn mode_ptrs(&self) -> OutputModeIterator<'_> {
         let ptr = self.0.as_ptr();
         let head = unsafe { &mut (*ptr).modes as *mut wl_list };
         OutputModeIterator {
             current: head,
-            head: head,
+            head,
             parent: &self.0,
         }
     }
 
-    pub fn preferred_mode(&self) -> Option<OutputMode> {
+    pub fn preferred_mode(&self) -> Option<ModeInfo> {
         let ptr = self.0.as_ptr();
+        let mode = unsafe { wlr_output_preferred_mode(ptr) };
+        (!mode.is_null()).then(|| ModeInfo::from_ptr(mode))
+    }
 
+    /// The mode currently in use, or None if the output is disabled or uses a
+    /// custom mode.
+    pub fn current_mode(&self) -> Option<ModeInfo> {
+        let mode = unsafe { (*self.0.as_ptr()).current_mode };
+        (!mode.is_null()).then(|| ModeInfo::from_ptr(mode))
+    }
+
+    /// Selects the best mode with the given size. If a refresh rate (in mHz)
+    /// is given, only modes within `tolerance` mHz of it are considered, and
+    /// the closest wins; otherwise, the fastest mode wins. Ties are broken in
+    /// favor of the preferred mode.
+    pub fn select_mode(
+        &self,
+        width: i32,
+        height: i32,
+        refresh: Option<i32>,
+        tolerance: i32,
+    ) -> Option<ModeInfo> {
+        select_mode(self.modes(), width, height, refresh, tolerance)
+    }
+
+    /// Finds the live wlroots mode matching an owned mode. The pointer is only
+    /// valid until control is returned to wlroots.
+    pub fn mode_ptr(&self, mode: &ModeInfo) -> Option<*mut wlr_output_mode> {
+        self.mode_ptrs()
+            .find(|&m| ModeInfo::from_ptr(m).same_timing(mode))
+    }
+
+    /// Sets the pending mode. If the mode isn't one advertised by the output,
+    /// it's applied as a custom mode.
+    pub fn set_mode(&self, mode: &ModeInfo) {
+        let ptr = self.0.as_ptr();
         unsafe {
-            match wlr_output_preferred_mode(ptr).as_mut() {
-                Some(mode) => Some(OutputMode(ChildHandle::new(mode, self.handle()))),
-                None => None,
+            match self.mode_ptr(mode) {
+                Some(m) => wlr_output_set_mode(ptr, m),
+                None => wlr_output_set_custom_mode(ptr, mode.width, mode.height, mode.refresh),
             }
         }
     }
 
-    pub fn set_mode(&self, mode: OutputMode) {
-        unsafe { wlr_output_set_mode(self.0.as_ptr(), mode.0.as_ptr()) }
-    }
-
     pub fn enable(&self, enable: bool) {
         unsafe { wlr_output_enable(self.0.as_ptr(), enable) }
     }
@@ -56,6 +182,40 @@ impl Output {
         }
     }
 
+    /// Atomically applies a pending state to the output.
+    pub fn commit_state(&self, state: &OutputState) -> Result<(), WlrError> {
+        unsafe {
+            if !wlr_output_commit_state(self.0.try_as_ptr()?, state.as_ptr()) {
+                Err(WlrError::CallFailed("wlr_output_commit_state".into()))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks whether a pending state would be accepted by the backend,
+    /// without applying it.
+    pub fn test_state(&self, state: &OutputState) -> bool {
+        unsafe { wlr_output_test_state(self.0.as_ptr(), state.as_ptr()) }
+    }
+
+    /// Asks the backend for a frame event, for example because something on
+    /// screen has changed.
+    pub fn schedule_frame(&self) {
+        unsafe { wlr_output_schedule_frame(self.0.as_ptr()) }
+    }
+
+    /// Returns true if a frame has been submitted and not yet presented.
+    pub fn frame_pending(&self) -> bool {
+        unsafe { (*self.0.as_ptr()).frame_pending }
+    }
+
+    /// Forces cursors to be drawn in software while the number of locks
+    /// exceeds the number of unlocks, for example during screen capture.
+    pub fn lock_software_cursors(&self, lock: bool) {
+        unsafe { wlr_output_lock_software_cursors(self.0.as_ptr(), lock) }
+    }
+
     pub fn init_render(
         &mut self,
         allocator: &Allocator,
@@ -79,6 +239,115 @@ impl Output {
             (cb)();
         });
     }
+
+    /// Called when a buffer has been presented (or discarded) by the
+    /// display.
+    pub fn on_present(&mut self, cb: impl Fn(OutputPresentEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.present };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_event_present) };
+            (cb)(OutputPresentEvent {
+                commit_seq: event.commit_seq,
+                presented: event.presented,
+                when: unsafe { event.when.as_ref() }.map(duration_from_timespec),
+                seq: event.seq as u64,
+                refresh: event.refresh,
+                flags: PresentFlags(event.flags),
+            });
+        });
+    }
+
+    /// Called after a state has been successfully committed.
+    pub fn on_commit(&mut self, cb: impl Fn(OutputCommitEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.commit };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_event_commit) };
+            let state = unsafe { OutputState::from_ptr(event.state) };
+            (cb)(OutputCommitEvent {
+                committed: state.committed(),
+                when: unsafe { event.when.as_ref() }.map(duration_from_timespec),
+                state,
+            });
+        });
+    }
+
+    /// Called when the backend reports damage on the output, for example
+    /// when a software cursor moves.
+    pub fn on_damage(&mut self, cb: impl Fn(OutputDamageEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.damage };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_event_damage) };
+            (cb)(OutputDamageEvent {
+                damage: unsafe { Region::from_ptr(event.damage) },
+            });
+        });
+    }
+
+    /// Called when the output needs a new frame to be scheduled, for example
+    /// after a mode change.
+    pub fn on_needs_frame(&mut self, cb: impl Fn() + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.needs_frame };
+        self.0.add_listener(signal, move |_data| {
+            (cb)();
+        });
+    }
+
+    /// Called just before a state is committed. The pending state can be
+    /// inspected, but not modified.
+    pub fn on_precommit(&mut self, cb: impl Fn(&OutputPrecommitEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.precommit };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_event_precommit) };
+            (cb)(&OutputPrecommitEvent {
+                when: unsafe { event.when.as_ref() }.map(duration_from_timespec),
+                state: unsafe { OutputState::from_ptr(event.state) },
+            });
+        });
+    }
+
+    /// Called when a client binds to the wl_output global.
+    pub fn on_bind(&mut self, cb: impl Fn(OutputBindEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.bind };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_event_bind) };
+            let resource = unsafe { ObjectId::from_ptr(WlOutput::interface(), event.resource) };
+            if let Ok(resource) = resource {
+                (cb)(OutputBindEvent { resource });
+            }
+        });
+    }
+
+    /// Called when the backend requests a new state, for example when the
+    /// window of a nested backend is resized. The compositor should usually
+    /// pass the state to [`Output::commit_state`].
+    pub fn on_request_state(&mut self, cb: impl Fn(&OutputRequestStateEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.request_state };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_event_request_state) };
+            (cb)(&OutputRequestStateEvent {
+                state: unsafe { OutputState::from_ptr(event.state) },
+            });
+        });
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.try_as_ptr().is_err() {
+            return f.write_str("Output(<destroyed>)");
+        }
+
+        f.debug_struct("Output")
+            .field("name", &self.name())
+            .field("description", &self.description())
+            .field("make", &self.make())
+            .field("model", &self.model())
+            .field("serial", &self.serial())
+            .field("physical_size", &self.physical_size())
+            .field("subpixel", &self.subpixel())
+            .field("enabled", &self.enabled())
+            .finish()
+    }
 }
 
 impl Destroyable for wlr_output {
@@ -87,24 +356,178 @@ impl Destroyable for wlr_output {
     }
 }
 
-pub struct OutputMode<'parent>(ChildHandle<'parent, wlr_output_mode, wlr_output>);
+/// Flags describing how a frame was presented, mirroring
+/// `enum wlr_output_present_flag`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct PresentFlags(u32);
+
+impl PresentFlags {
+    pub const VSYNC: Self = Self(wlr_output_present_flag_WLR_OUTPUT_PRESENT_VSYNC as u32);
+    pub const HW_CLOCK: Self = Self(wlr_output_present_flag_WLR_OUTPUT_PRESENT_HW_CLOCK as u32);
+    pub const HW_COMPLETION: Self =
+        Self(wlr_output_present_flag_WLR_OUTPUT_PRESENT_HW_COMPLETION as u32);
+    pub const ZERO_COPY: Self = Self(wlr_output_present_flag_WLR_OUTPUT_PRESENT_ZERO_COPY as u32);
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct OutputPresentEvent {
+    /// The sequence number of the commit that was presented.
+    pub commit_seq: u32,
+    /// False if the frame was discarded rather than displayed.
+    pub presented: bool,
+    /// The time at which the frame was presented, on the monotonic clock.
+    pub when: Option<Duration>,
+    /// The vertical retrace counter, or zero if unavailable.
+    pub seq: u64,
+    /// The prediction of how many nanoseconds after `when` the next frame will
+    /// be presented, or zero if unknown.
+    pub refresh: i32,
+    pub flags: PresentFlags,
+}
+
+#[derive(Clone, Copy)]
+pub struct OutputCommitEvent<'a> {
+    /// The fields that changed in this commit.
+    pub committed: OutputStateFields,
+    pub when: Option<Duration>,
+    state: &'a OutputState,
+}
+
+impl OutputCommitEvent<'_> {
+    /// The buffer that was committed, if the commit included one. This takes
+    /// a new lock on the buffer, which keeps it out of the output's
+    /// swapchain until it's dropped.
+    pub fn buffer(&self) -> Option<Buffer> {
+        self.state.buffer()
+    }
+}
+
+impl std::fmt::Debug for OutputCommitEvent<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("OutputCommitEvent")
+            .field("committed", &self.committed)
+            .field("when", &self.when)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputDamageEvent {
+    /// The damaged region, in output buffer-local coordinates.
+    pub damage: Region,
+}
+
+pub struct OutputPrecommitEvent<'a> {
+    pub when: Option<Duration>,
+    pub state: &'a OutputState,
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputBindEvent {
+    /// The wl_output resource created for the client.
+    pub resource: ObjectId,
+}
+
+pub struct OutputRequestStateEvent<'a> {
+    pub state: &'a OutputState,
+}
+
+pub(crate) fn duration_from_timespec(ts: &timespec) -> Duration {
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+/// The picture aspect ratio of a mode, mirroring
+/// `enum wlr_output_mode_aspect_ratio`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum AspectRatio {
+    #[default]
+    None,
+    Ratio4By3,
+    Ratio16By9,
+    Ratio64By27,
+    Ratio256By135,
+}
+
+impl AspectRatio {
+    fn from_raw(raw: wlr_output_mode_aspect_ratio) -> Self {
+        match raw {
+            wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_4_3 => Self::Ratio4By3,
+            wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_16_9 => Self::Ratio16By9,
+            wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_64_27 => Self::Ratio64By27,
+            wlr_output_mode_aspect_ratio_WLR_OUTPUT_MODE_ASPECT_RATIO_256_135 => {
+                Self::Ratio256By135
+            }
+            _ => Self::None,
+        }
+    }
+}
+
+/// An owned copy of an output mode. Modes are ordered by pixel area, then by
+/// width and height (so that modes with the same area sort consistently),
+/// then by refresh rate, so the "largest" mode sorts last.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModeInfo {
+    pub width: i32,
+    pub height: i32,
+    /// The refresh rate in mHz, or zero if unknown.
+    pub refresh: i32,
+    pub preferred: bool,
+    pub picture_aspect_ratio: AspectRatio,
+}
+
+impl ModeInfo {
+    pub(crate) fn from_ptr(mode: *const wlr_output_mode) -> Self {
+        let mode = unsafe { &*mode };
+        Self {
+            width: mode.width,
+            height: mode.height,
+            refresh: mode.refresh,
+            preferred: mode.preferred,
+            picture_aspect_ratio: AspectRatio::from_raw(mode.picture_aspect_ratio),
+        }
+    }
 
-impl OutputMode<'_> {
     pub fn dimensions(&self) -> (i32, i32) {
-        let p = self.0.as_ptr();
-        unsafe { ((*p).width, (*p).height) }
+        (self.width, self.height)
     }
 
-    pub fn refresh(&self) -> i32 {
-        unsafe { (*self.0.as_ptr()).refresh }
+    /// Returns true if the mode has the same size and refresh rate as the
+    /// other, ignoring the preferred flag.
+    pub fn same_timing(&self, other: &ModeInfo) -> bool {
+        self.width == other.width
+            && self.height == other.height
+            && self.refresh == other.refresh
+            && self.picture_aspect_ratio == other.picture_aspect_ratio
     }
 
-    pub fn preferred(&self) -> bool {
-        unsafe { (*self.0.as_ptr()).preferred }
+    fn area(&self) -> i64 {
+        self.width as i64 * self.height as i64
+    }
+}
+
+impl PartialOrd for ModeInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    pub fn picture_aspect_ratio(&self) -> (i32, i32) {
-        unsafe { ((*self.0.as_ptr()).width, (*self.0.as_ptr()).height) }
+impl Ord for ModeInfo {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.area()
+            .cmp(&other.area())
+            .then(self.width.cmp(&other.width))
+            .then(self.height.cmp(&other.height))
+            .then(self.refresh.cmp(&other.refresh))
+            .then(self.preferred.cmp(&other.preferred))
+            .then(self.picture_aspect_ratio.cmp(&other.picture_aspect_ratio))
     }
 }
 
@@ -115,7 +538,7 @@ struct OutputModeIterator<'parent> {
 }
 
 impl<'parent> Iterator for OutputModeIterator<'parent> {
-    type Item = OutputMode<'parent>;
+    type Item = *mut wlr_output_mode;
 
     fn next(&mut self) -> Option<Self::Item> {
         // Check that the parent is still valid.
@@ -127,9 +550,135 @@ impl<'parent> Iterator for OutputModeIterator<'parent> {
             } else {
                 let mode = container_of!((*self.current).next, wlr_output_mode, link)
                     as *mut wlr_output_mode;
-                self.current = (*mode).link.next;
-                Some(OutputMode(ChildHandle::new(mode, self.parent)))
+                self.current = &mut (*mode).link;
+                Some(mode)
             }
         }
     }
 }
+
+/// The mode selection behind [`Output::select_mode`].
+fn select_mode(
+    modes: impl Iterator<Item = ModeInfo>,
+    width: i32,
+    height: i32,
+    refresh: Option<i32>,
+    tolerance: i32,
+) -> Option<ModeInfo> {
+    let candidates = modes.filter(|m| m.width == width && m.height == height);
+
+    match refresh {
+        Some(refresh) => candidates
+            .filter(|m| (m.refresh - refresh).abs() <= tolerance)
+            .min_by_key(|m| ((m.refresh - refresh).abs(), !m.preferred)),
+        None => candidates.max_by_key(|m| (m.refresh, m.preferred)),
+    }
+}
+
+/// Copies a nullable C string owned by wlroots.
+unsafe fn string_from_ptr(ptr: *const c_char) -> Option<String> {
+    ptr.as_ref()
+        .map(|p| CStr::from_ptr(p).to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mode(width: i32, height: i32, refresh: i32, preferred: bool) -> ModeInfo {
+        ModeInfo {
+            width,
+            height,
+            refresh,
+            preferred,
+            picture_aspect_ratio: AspectRatio::None,
+        }
+    }
+
+    #[test]
+    fn modes_order_by_area_then_size_then_refresh() {
+        let mut modes = vec![
+            mode(1920, 1080, 60000, false),
+            mode(1280, 720, 60000, false),
+            mode(1920, 1080, 144000, false),
+            mode(1080, 1920, 60000, false),
+            mode(2560, 1440, 30000, false),
+        ];
+        modes.sort();
+
+        let order: Vec<_> = modes
+            .iter()
+            .map(|m| (m.width, m.height, m.refresh))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                (1280, 720, 60000),
+                (1080, 1920, 60000),
+                (1920, 1080, 60000),
+                (1920, 1080, 144000),
+                (2560, 1440, 30000),
+            ]
+        );
+    }
+
+    #[test]
+    fn same_timing_ignores_preferred() {
+        assert!(mode(1920, 1080, 60000, true).same_timing(&mode(1920, 1080, 60000, false)));
+        assert!(!mode(1920, 1080, 60000, true).same_timing(&mode(1920, 1080, 59940, true)));
+    }
+
+    #[test]
+    fn select_mode_without_refresh_picks_fastest() {
+        let modes = [
+            mode(1920, 1080, 60000, true),
+            mode(1920, 1080, 144000, false),
+            mode(2560, 1440, 165000, false),
+        ];
+
+        let selected = select_mode(modes.into_iter(), 1920, 1080, None, 0).unwrap();
+        assert_eq!(selected.refresh, 144000);
+    }
+
+    #[test]
+    fn select_mode_with_refresh_picks_closest_within_tolerance() {
+        let modes = [
+            mode(1920, 1080, 59940, false),
+            mode(1920, 1080, 60000, false),
+            mode(1920, 1080, 75000, false),
+        ];
+
+        let selected = select_mode(modes.into_iter(), 1920, 1080, Some(59950), 100).unwrap();
+        assert_eq!(selected.refresh, 59940);
+
+        assert_eq!(
+            select_mode(modes.into_iter(), 1920, 1080, Some(120000), 500),
+            None
+        );
+    }
+
+    #[test]
+    fn select_mode_breaks_ties_with_preferred() {
+        let modes = [
+            mode(1920, 1080, 60000, false),
+            mode(1920, 1080, 60000, true),
+        ];
+
+        assert!(
+            select_mode(modes.into_iter(), 1920, 1080, None, 0)
+                .unwrap()
+                .preferred
+        );
+        assert!(
+            select_mode(modes.into_iter(), 1920, 1080, Some(60000), 0)
+                .unwrap()
+                .preferred
+        );
+    }
+
+    #[test]
+    fn select_mode_requires_exact_size() {
+        let modes = [mode(1920, 1200, 60000, true)];
+        assert_eq!(select_mode(modes.into_iter(), 1920, 1080, None, 0), None);
+    }
+}
@@ -0,0 +1,133 @@
+use wlroots_sys::{
+    wlr_output_cursor, wlr_output_cursor_create, wlr_output_cursor_destroy, wlr_output_cursor_move,
+    wlr_output_cursor_set_buffer,
+};
+
+use crate::{Buffer, Output, WlrError, DRM_FORMAT_ARGB8888};
+
+/// A cursor image on an output, equivalent to `struct wlr_output_cursor`.
+/// wlroots puts the cursor on a hardware plane if the backend supports it,
+/// and otherwise falls back to drawing it in software, during rendering.
+///
+/// wlroots only takes cursor images as buffers, so there's no way to set one
+/// from a texture; render the texture into a buffer first.
+///
+/// Cursors are destroyed along with their output.
+pub struct OutputCursor {
+    ptr: *mut wlr_output_cursor,
+    output: Output,
+}
+
+impl OutputCursor {
+    pub fn new(output: &Output) -> Result<Self, WlrError> {
+        let output_ptr = output.handle().try_as_ptr()?;
+        let ptr = unsafe { wlr_output_cursor_create(output_ptr) };
+        if ptr.is_null() {
+            return Err(WlrError::CallFailed("wlr_output_cursor_create".into()));
+        }
+
+        Ok(Self {
+            ptr,
+            output: Output::from_ptr(output_ptr),
+        })
+    }
+
+    /// Returns the underlying pointer, checking that the output is still
+    /// alive.
+    pub fn try_as_ptr(&self) -> Result<*mut wlr_output_cursor, WlrError> {
+        let _ = self.output.handle().try_as_ptr()?;
+        Ok(self.ptr)
+    }
+
+    /// Sets the cursor image. The hotspot is in buffer coordinates.
+    pub fn set_buffer(
+        &self,
+        buffer: &Buffer,
+        hotspot_x: i32,
+        hotspot_y: i32,
+    ) -> Result<(), WlrError> {
+        let ptr = self.try_as_ptr()?;
+        let buffer = buffer.handle().try_as_ptr()?;
+
+        unsafe {
+            if !wlr_output_cursor_set_buffer(ptr, buffer, hotspot_x, hotspot_y) {
+                return Err(WlrError::CallFailed("wlr_output_cursor_set_buffer".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sets the cursor image from premultiplied ARGB8888 pixels, with a stride
+    /// of `width * 4`.
+    pub fn set_image(
+        &self,
+        width: i32,
+        height: i32,
+        pixels: Vec<u8>,
+        hotspot_x: i32,
+        hotspot_y: i32,
+    ) -> Result<(), WlrError> {
+        let buffer = Buffer::from_pixels(
+            width,
+            height,
+            DRM_FORMAT_ARGB8888,
+            width as usize * 4,
+            pixels,
+        )?;
+
+        // The cursor keeps its own lock on the buffer.
+        self.set_buffer(&buffer, hotspot_x, hotspot_y)
+    }
+
+    /// Hides the cursor.
+    pub fn hide(&self) -> Result<(), WlrError> {
+        let ptr = self.try_as_ptr()?;
+        unsafe { wlr_output_cursor_set_buffer(ptr, std::ptr::null_mut(), 0, 0) };
+        Ok(())
+    }
+
+    /// Moves the cursor, in output-buffer coordinates.
+    pub fn move_to(&self, x: f64, y: f64) -> Result<(), WlrError> {
+        let ptr = self.try_as_ptr()?;
+
+        unsafe {
+            if !wlr_output_cursor_move(ptr, x, y) {
+                return Err(WlrError::CallFailed("wlr_output_cursor_move".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn position(&self) -> Result<(f64, f64), WlrError> {
+        let ptr = self.try_as_ptr()?;
+        unsafe { Ok(((*ptr).x, (*ptr).y)) }
+    }
+
+    /// Returns true if the cursor has an image and is at least partly on the
+    /// output.
+    pub fn visible(&self) -> bool {
+        match self.try_as_ptr() {
+            Ok(ptr) => unsafe { (*ptr).enabled && (*ptr).visible },
+            Err(_) => false,
+        }
+    }
+
+    /// Returns true if the cursor is currently displayed on a hardware plane,
+    /// rather than in software.
+    pub fn is_hardware(&self) -> bool {
+        match self.output.handle().try_as_ptr() {
+            Ok(output) => unsafe { (*output).hardware_cursor == self.ptr },
+            Err(_) => false,
+        }
+    }
+}
+
+impl Drop for OutputCursor {
+    fn drop(&mut self) {
+        if let Ok(ptr) = self.try_as_ptr() {
+            unsafe { wlr_output_cursor_destroy(ptr) }
+        }
+    }
+}
@@ -1,10 +1,35 @@
-use wayland_sys::server::wl_signal;
+use wayland_sys::{common::wl_list, server::wl_signal};
 use wlroots_sys::{
-    wlr_output_layout, wlr_output_layout_add_auto, wlr_output_layout_create,
-    wlr_output_layout_destroy,
+    wlr_box, wlr_direction, wlr_direction_WLR_DIRECTION_DOWN, wlr_direction_WLR_DIRECTION_LEFT,
+    wlr_direction_WLR_DIRECTION_RIGHT, wlr_direction_WLR_DIRECTION_UP, wlr_output,
+    wlr_output_layout, wlr_output_layout_add, wlr_output_layout_add_auto,
+    wlr_output_layout_adjacent_output, wlr_output_layout_closest_point,
+    wlr_output_layout_contains_point, wlr_output_layout_create, wlr_output_layout_destroy,
+    wlr_output_layout_get, wlr_output_layout_get_box, wlr_output_layout_intersects,
+    wlr_output_layout_output, wlr_output_layout_output_at, wlr_output_layout_remove,
 };
 
-use crate::{Destroyable, Handle, Output, WlrError};
+use crate::{macros::*, Destroyable, Handle, Output, Rect, WlrError};
+
+/// A direction in the layout, used to find neighboring outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    fn as_raw(self) -> wlr_direction {
+        match self {
+            Direction::Up => wlr_direction_WLR_DIRECTION_UP,
+            Direction::Down => wlr_direction_WLR_DIRECTION_DOWN,
+            Direction::Left => wlr_direction_WLR_DIRECTION_LEFT,
+            Direction::Right => wlr_direction_WLR_DIRECTION_RIGHT,
+        }
+    }
+}
 
 pub struct OutputLayout(Box<Handle<wlr_output_layout>>);
 
@@ -17,15 +42,223 @@ impl OutputLayout {
         }
     }
 
-    pub fn add_auto(&self, output: &Output) {
+    /// Adds an output at a fixed position, in layout coordinates. If the
+    /// output is already in the layout, it's moved.
+    pub fn add(&self, output: &Output, x: i32, y: i32) -> Result<LayoutOutput, WlrError> {
+        let ptr = unsafe { wlr_output_layout_add(self.0.as_ptr(), output.handle().as_ptr(), x, y) };
+        if ptr.is_null() {
+            return Err(WlrError::CallFailed("wlr_output_layout_add".into()));
+        }
+
+        Ok(LayoutOutput::from_ptr(ptr))
+    }
+
+    /// Adds an output to the right of the existing outputs. Automatically
+    /// placed outputs are rearranged when other outputs are added or removed.
+    pub fn add_auto(&self, output: &Output) -> Result<LayoutOutput, WlrError> {
+        let ptr = unsafe { wlr_output_layout_add_auto(self.0.as_ptr(), output.handle().as_ptr()) };
+        if ptr.is_null() {
+            return Err(WlrError::CallFailed("wlr_output_layout_add_auto".into()));
+        }
+
+        Ok(LayoutOutput::from_ptr(ptr))
+    }
+
+    /// The layout entry for an output, if it's in the layout.
+    pub fn get(&self, output: &Output) -> Option<LayoutOutput> {
+        let ptr = unsafe { wlr_output_layout_get(self.0.as_ptr(), output.handle().as_ptr()) };
+        (!ptr.is_null()).then(|| LayoutOutput::from_ptr(ptr))
+    }
+
+    pub fn remove(&self, output: &Output) {
+        unsafe {
+            wlr_output_layout_remove(self.0.as_ptr(), output.handle().as_ptr());
+        }
+    }
+
+    /// The box occupied by an output, in layout coordinates, or None if the
+    /// output isn't in the layout or is disabled.
+    pub fn get_box(&self, output: &Output) -> Option<Rect> {
+        let rect = self.box_of(output.handle().as_ptr());
+        (!rect.is_empty()).then_some(rect)
+    }
+
+    /// The bounding box of all the outputs in the layout.
+    pub fn bounding_box(&self) -> Rect {
+        self.box_of(std::ptr::null_mut())
+    }
+
+    fn box_of(&self, output: *mut wlr_output) -> Rect {
+        let mut b = wlr_box {
+            x: 0,
+            y: 0,
+            width: 0,
+            height: 0,
+        };
+
+        unsafe { wlr_output_layout_get_box(self.0.as_ptr(), output, &mut b) };
+        b.into()
+    }
+
+    /// The output at a point in layout coordinates, if any.
+    pub fn output_at(&self, x: f64, y: f64) -> Option<Output> {
+        let output = unsafe { wlr_output_layout_output_at(self.0.as_ptr(), x, y) };
+        (!output.is_null()).then(|| Output::from_ptr(output))
+    }
+
+    /// The closest point to (x, y) that lies on an output. If `reference` is
+    /// given, the point is constrained to that output.
+    pub fn closest_point(&self, reference: Option<&Output>, x: f64, y: f64) -> (f64, f64) {
+        let reference = reference.map_or(std::ptr::null_mut(), |o| o.handle().as_ptr());
+        let (mut dest_x, mut dest_y) = (0.0, 0.0);
+
+        unsafe {
+            wlr_output_layout_closest_point(
+                self.0.as_ptr(),
+                reference,
+                x,
+                y,
+                &mut dest_x,
+                &mut dest_y,
+            );
+        }
+
+        (dest_x, dest_y)
+    }
+
+    /// Returns true if the point lies on the given output, or on any output if
+    /// `reference` is None.
+    pub fn contains_point(&self, reference: Option<&Output>, x: i32, y: i32) -> bool {
+        let reference = reference.map_or(std::ptr::null_mut(), |o| o.handle().as_ptr());
+        unsafe { wlr_output_layout_contains_point(self.0.as_ptr(), reference, x, y) }
+    }
+
+    /// Returns true if the box, in layout coordinates, overlaps the given
+    /// output, or any output if `reference` is None.
+    pub fn intersects(&self, reference: Option<&Output>, rect: Rect) -> bool {
+        let reference = reference.map_or(std::ptr::null_mut(), |o| o.handle().as_ptr());
+        let b: wlr_box = rect.into();
+        unsafe { wlr_output_layout_intersects(self.0.as_ptr(), reference, &b) }
+    }
+
+    /// The closest output in the given direction from a reference output,
+    /// measured from a point in layout coordinates.
+    pub fn adjacent_output(
+        &self,
+        direction: Direction,
+        reference: &Output,
+        ref_x: f64,
+        ref_y: f64,
+    ) -> Option<Output> {
+        let output = unsafe {
+            wlr_output_layout_adjacent_output(
+                self.0.as_ptr(),
+                direction.as_raw(),
+                reference.handle().as_ptr(),
+                ref_x,
+                ref_y,
+            )
+        };
+
+        (!output.is_null()).then(|| Output::from_ptr(output))
+    }
+
+    /// The outputs in the layout, along with the layout coordinates of their
+    /// top-left corners.
+    pub fn outputs(&self) -> Vec<(Output, i32, i32)> {
+        self.layout_outputs()
+            .into_iter()
+            .map(|l_output| {
+                let (x, y) = l_output.position();
+                (l_output.output(), x, y)
+            })
+            .collect()
+    }
+
+    /// The entries of the layout, in the order they were added.
+    pub fn layout_outputs(&self) -> Vec<LayoutOutput> {
+        let mut l_outputs = Vec::new();
+
         unsafe {
-            wlr_output_layout_add_auto(self.0.as_ptr(), output.handle().as_ptr());
+            let head = &mut (*self.0.as_ptr()).outputs as *mut wl_list;
+            list_for_each!(l_output, head, wlr_output_layout_output, link, {
+                l_outputs.push(LayoutOutput::from_ptr(l_output));
+            });
         }
+
+        l_outputs
     }
 
     pub fn handle(&self) -> &Handle<wlr_output_layout> {
         &self.0
     }
+
+    /// Called when an output is added to the layout.
+    pub fn on_add(&mut self, cb: impl Fn(LayoutOutput) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.add };
+        self.0.add_listener(signal, move |data| {
+            (cb)(LayoutOutput::from_ptr(
+                data as *mut wlr_output_layout_output,
+            ));
+        });
+    }
+
+    /// Called whenever the layout changes: when an output is added, removed
+    /// or moved, or when an output in the layout changes mode, scale or
+    /// transform.
+    pub fn on_change(&mut self, cb: impl Fn() + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.change };
+        self.0.add_listener(signal, move |_data| {
+            (cb)();
+        });
+    }
+
+    /// Called just before the layout is destroyed.
+    pub fn on_destroy(&mut self, cb: impl Fn() + 'static) {
+        self.0.add_destroy_callback(cb);
+    }
+}
+
+/// An entry in an output layout, equivalent to
+/// `struct wlr_output_layout_output`. The entry is destroyed when the output
+/// is removed from the layout.
+pub struct LayoutOutput(Box<Handle<wlr_output_layout_output>>);
+
+impl LayoutOutput {
+    pub fn from_ptr(ptr: *mut wlr_output_layout_output) -> Self {
+        Self(Handle::new(ptr))
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_output_layout_output> {
+        &self.0
+    }
+
+    pub fn output(&self) -> Output {
+        Output::from_ptr(unsafe { (*self.0.as_ptr()).output })
+    }
+
+    /// The layout coordinates of the output's top-left corner.
+    pub fn position(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).x, (*p).y) }
+    }
+
+    /// True if the output was added with [`OutputLayout::add_auto`], and is
+    /// placed automatically.
+    pub fn auto_configured(&self) -> bool {
+        unsafe { (*self.0.as_ptr()).auto_configured }
+    }
+
+    /// Called just before the entry is destroyed.
+    pub fn on_destroy(&mut self, cb: impl Fn() + 'static) {
+        self.0.add_destroy_callback(cb);
+    }
+}
+
+impl Destroyable for wlr_output_layout_output {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
 }
 
 impl Drop for OutputLayout {
@@ -0,0 +1,241 @@
+use wayland_server::{protocol::wl_output::Transform, DisplayHandle};
+use wayland_sys::{common::wl_list, server::wl_signal};
+use wlroots_sys::{
+    wlr_output_configuration_head_v1, wlr_output_configuration_head_v1_create,
+    wlr_output_configuration_v1, wlr_output_configuration_v1_create,
+    wlr_output_configuration_v1_destroy, wlr_output_configuration_v1_send_failed,
+    wlr_output_configuration_v1_send_succeeded, wlr_output_layout_get, wlr_output_manager_v1,
+    wlr_output_manager_v1_create, wlr_output_manager_v1_set_configuration,
+};
+
+use crate::{
+    macros::*, Destroyable, Handle, ModeInfo, Output, OutputLayout, OutputState, WlrError,
+};
+
+/// Implements the wlr-output-management-unstable-v1 protocol, which lets
+/// tools like wlr-randr and kanshi inspect and reconfigure outputs.
+pub struct OutputManager(Box<Handle<wlr_output_manager_v1>>);
+
+impl OutputManager {
+    pub fn new(display: &DisplayHandle) -> Result<Self, WlrError> {
+        let display = display.backend_handle().display_ptr();
+        let ptr = unsafe { wlr_output_manager_v1_create(display).as_mut() };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed("wlr_output_manager_v1_create".into())),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_output_manager_v1> {
+        &self.0
+    }
+
+    /// Publishes the current configuration to clients, with one head per
+    /// output. Positions are taken from the layout; outputs that aren't in the
+    /// layout are advertised at (0, 0). This should be called whenever an
+    /// output is added, removed or changed.
+    pub fn set_configuration<'a>(
+        &self,
+        outputs: impl IntoIterator<Item = &'a Output>,
+        layout: &OutputLayout,
+    ) -> Result<(), WlrError> {
+        let manager = self.0.try_as_ptr()?;
+        let layout = layout.handle().try_as_ptr()?;
+
+        unsafe {
+            let config = wlr_output_configuration_v1_create();
+            if config.is_null() {
+                return Err(WlrError::CallFailed(
+                    "wlr_output_configuration_v1_create".into(),
+                ));
+            }
+
+            for output in outputs {
+                let output = output.handle().try_as_ptr()?;
+                let head = wlr_output_configuration_head_v1_create(config, output);
+                if head.is_null() {
+                    wlr_output_configuration_v1_destroy(config);
+                    return Err(WlrError::CallFailed(
+                        "wlr_output_configuration_head_v1_create".into(),
+                    ));
+                }
+
+                if let Some(l_output) = wlr_output_layout_get(layout, output).as_ref() {
+                    (*head).state.x = l_output.x;
+                    (*head).state.y = l_output.y;
+                }
+            }
+
+            // The manager takes ownership of the configuration.
+            wlr_output_manager_v1_set_configuration(manager, config);
+        }
+
+        Ok(())
+    }
+
+    /// Called when a client asks for a configuration to be applied. The
+    /// compositor should try to apply it, and then reply with the result.
+    pub fn on_apply(&mut self, cb: impl Fn(OutputConfiguration) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.apply };
+        self.0.add_listener(signal, move |data| {
+            (cb)(OutputConfiguration::from_ptr(
+                data as *mut wlr_output_configuration_v1,
+            ));
+        });
+    }
+
+    /// Called when a client asks whether a configuration would be accepted,
+    /// without applying it.
+    pub fn on_test(&mut self, cb: impl Fn(OutputConfiguration) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.test };
+        self.0.add_listener(signal, move |data| {
+            (cb)(OutputConfiguration::from_ptr(
+                data as *mut wlr_output_configuration_v1,
+            ));
+        });
+    }
+}
+
+impl Destroyable for wlr_output_manager_v1 {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
+
+/// The result of applying or testing a configuration. Configurations that
+/// became outdated because the outputs changed are cancelled by wlroots
+/// itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigurationResult {
+    Succeeded,
+    Failed,
+}
+
+/// A configuration requested by a client. The compositor owns the
+/// configuration, and must reply to it exactly once; if it's dropped without
+/// a reply, the client is told that it failed.
+pub struct OutputConfiguration {
+    ptr: *mut wlr_output_configuration_v1,
+    heads: Vec<OutputConfigurationHead>,
+    replied: bool,
+}
+
+impl OutputConfiguration {
+    fn from_ptr(ptr: *mut wlr_output_configuration_v1) -> Self {
+        let mut heads = Vec::new();
+
+        unsafe {
+            let head_list = &mut (*ptr).heads as *mut wl_list;
+            list_for_each!(head, head_list, wlr_output_configuration_head_v1, link, {
+                heads.push(OutputConfigurationHead::from_ptr(head));
+            });
+        }
+
+        Self {
+            ptr,
+            heads,
+            replied: false,
+        }
+    }
+
+    /// The requested state of each output. Outputs that aren't mentioned
+    /// should be left unchanged.
+    pub fn heads(&self) -> &[OutputConfigurationHead] {
+        &self.heads
+    }
+
+    pub fn reply(mut self, result: ConfigurationResult) {
+        self.send(result);
+    }
+
+    fn send(&mut self, result: ConfigurationResult) {
+        if self.replied {
+            return;
+        }
+
+        self.replied = true;
+        unsafe {
+            match result {
+                ConfigurationResult::Succeeded => {
+                    wlr_output_configuration_v1_send_succeeded(self.ptr)
+                }
+                ConfigurationResult::Failed => wlr_output_configuration_v1_send_failed(self.ptr),
+            }
+        }
+    }
+}
+
+impl Drop for OutputConfiguration {
+    fn drop(&mut self) {
+        self.send(ConfigurationResult::Failed);
+        unsafe { wlr_output_configuration_v1_destroy(self.ptr) }
+    }
+}
+
+/// The requested state for a single output.
+pub struct OutputConfigurationHead {
+    pub output: Output,
+    pub enabled: bool,
+    /// The mode in the head, either one advertised by the output or a custom
+    /// one. Clients send the whole state of each head, so this is usually
+    /// set even if the mode didn't change; it's None only if the head has
+    /// no mode at all, which can happen for disabled outputs.
+    pub mode: Option<ModeInfo>,
+    pub x: i32,
+    pub y: i32,
+    pub transform: Transform,
+    pub scale: f32,
+    pub adaptive_sync_enabled: bool,
+}
+
+impl OutputConfigurationHead {
+    fn from_ptr(ptr: *mut wlr_output_configuration_head_v1) -> Self {
+        let state = unsafe { &(*ptr).state };
+
+        let mode = if !state.mode.is_null() {
+            Some(ModeInfo::from_ptr(state.mode))
+        } else if state.custom_mode.width > 0 && state.custom_mode.height > 0 {
+            Some(ModeInfo {
+                width: state.custom_mode.width,
+                height: state.custom_mode.height,
+                refresh: state.custom_mode.refresh,
+                preferred: false,
+                picture_aspect_ratio: Default::default(),
+            })
+        } else {
+            None
+        };
+
+        Self {
+            output: Output::from_ptr(state.output),
+            enabled: state.enabled,
+            mode,
+            x: state.x,
+            y: state.y,
+            transform: Transform::try_from(state.transform as u32).unwrap_or(Transform::Normal),
+            scale: state.scale,
+            adaptive_sync_enabled: state.adaptive_sync_enabled,
+        }
+    }
+
+    /// Builds a pending state for the output from the head. The position
+    /// isn't part of the output state, and must be applied to the layout
+    /// separately.
+    pub fn to_state(&self) -> OutputState {
+        let mut state = OutputState::new();
+        state.set_enabled(self.enabled);
+
+        if self.enabled {
+            if let Some(mode) = &self.mode {
+                state.set_mode(&self.output, mode);
+            }
+
+            state.set_transform(self.transform);
+            state.set_scale(self.scale);
+            state.set_adaptive_sync_enabled(self.adaptive_sync_enabled);
+        }
+
+        state
+    }
+}
@@ -0,0 +1,77 @@
+use wayland_server::DisplayHandle;
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{
+    wlr_output_power_manager_v1, wlr_output_power_manager_v1_create,
+    wlr_output_power_v1_set_mode_event, zwlr_output_power_v1_mode_ZWLR_OUTPUT_POWER_V1_MODE_ON,
+};
+
+use crate::{Destroyable, Handle, Output, OutputState, WlrError};
+
+/// Implements the wlr-output-power-management-unstable-v1 protocol, used by
+/// idle daemons like swayidle to turn displays off and on (DPMS).
+pub struct OutputPowerManager(Box<Handle<wlr_output_power_manager_v1>>);
+
+impl OutputPowerManager {
+    pub fn new(display: &DisplayHandle) -> Result<Self, WlrError> {
+        let display = display.backend_handle().display_ptr();
+        let ptr = unsafe { wlr_output_power_manager_v1_create(display).as_mut() };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed(
+                "wlr_output_power_manager_v1_create".into(),
+            )),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_output_power_manager_v1> {
+        &self.0
+    }
+
+    /// Called when a client asks for an output to be turned on or off. In
+    /// most cases, the compositor can simply call
+    /// [`OutputPowerSetModeEvent::apply`].
+    pub fn on_set_mode(&mut self, cb: impl Fn(OutputPowerSetModeEvent) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.set_mode };
+        self.0.add_listener(signal, move |data| {
+            let event = unsafe { &*(data as *const wlr_output_power_v1_set_mode_event) };
+            let mode = if event.mode == zwlr_output_power_v1_mode_ZWLR_OUTPUT_POWER_V1_MODE_ON {
+                OutputPowerMode::On
+            } else {
+                OutputPowerMode::Off
+            };
+
+            (cb)(OutputPowerSetModeEvent {
+                output: Output::from_ptr(event.output),
+                mode,
+            });
+        });
+    }
+}
+
+impl Destroyable for wlr_output_power_manager_v1 {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OutputPowerMode {
+    On,
+    Off,
+}
+
+pub struct OutputPowerSetModeEvent {
+    pub output: Output,
+    pub mode: OutputPowerMode,
+}
+
+impl OutputPowerSetModeEvent {
+    /// Enables or disables the output to match the requested mode, and
+    /// commits the change.
+    pub fn apply(&self) -> Result<(), WlrError> {
+        let mut state = OutputState::new();
+        state.set_enabled(self.mode == OutputPowerMode::On);
+        self.output.commit_state(&state)
+    }
+}
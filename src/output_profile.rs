@@ -0,0 +1,555 @@
+use wayland_server::protocol::wl_output::Transform;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{ModeInfo, Output, OutputLayout, OutputState, WlrError};
+
+/// How far, in mHz, an output's refresh rate may be from the one in a
+/// profile and still be selected.
+const REFRESH_TOLERANCE: i32 = 500;
+
+/// The identifying metadata of an output, as reported by the backend.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutputIdentity {
+    pub name: String,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+impl OutputIdentity {
+    pub fn of(output: &Output) -> Self {
+        Self {
+            name: output.name(),
+            make: output.make(),
+            model: output.model(),
+            serial: output.serial(),
+        }
+    }
+}
+
+/// Criteria for matching an output. Fields that are None match anything.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct OutputMatcher {
+    /// The connector name, like "eDP-1". Names aren't stable across docks,
+    /// so make, model and serial are preferred.
+    pub name: Option<String>,
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+impl OutputMatcher {
+    /// Returns how specifically the matcher matches the output, or None if it
+    /// doesn't match at all.
+    pub fn score(&self, identity: &OutputIdentity) -> Option<u32> {
+        fn field(want: &Option<String>, have: Option<&str>, weight: u32) -> Option<u32> {
+            match want {
+                None => Some(0),
+                Some(want) if Some(want.as_str()) == have => Some(weight),
+                Some(_) => None,
+            }
+        }
+
+        Some(
+            field(&self.name, Some(identity.name.as_str()), 1)?
+                + field(&self.make, identity.make.as_deref(), 2)?
+                + field(&self.model, identity.model.as_deref(), 4)?
+                + field(&self.serial, identity.serial.as_deref(), 8)?,
+        )
+    }
+}
+
+/// A requested mode. If the refresh rate (in mHz) is omitted, the fastest
+/// mode with the given size is used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ModeSpec {
+    pub width: i32,
+    pub height: i32,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub refresh: Option<i32>,
+}
+
+/// A serializable mirror of `wl_output.transform`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+pub enum ProfileTransform {
+    #[default]
+    Normal,
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    Flipped,
+    Flipped90,
+    Flipped180,
+    Flipped270,
+}
+
+impl From<ProfileTransform> for Transform {
+    fn from(t: ProfileTransform) -> Self {
+        match t {
+            ProfileTransform::Normal => Transform::Normal,
+            ProfileTransform::Rotate90 => Transform::_90,
+            ProfileTransform::Rotate180 => Transform::_180,
+            ProfileTransform::Rotate270 => Transform::_270,
+            ProfileTransform::Flipped => Transform::Flipped,
+            ProfileTransform::Flipped90 => Transform::Flipped90,
+            ProfileTransform::Flipped180 => Transform::Flipped180,
+            ProfileTransform::Flipped270 => Transform::Flipped270,
+        }
+    }
+}
+
+/// The configuration for one output in a profile. Settings that are None
+/// are left as they are.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutputProfileEntry {
+    #[cfg_attr(feature = "serde", serde(rename = "match"))]
+    pub matcher: OutputMatcher,
+    #[cfg_attr(feature = "serde", serde(default = "default_enabled"))]
+    pub enabled: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub mode: Option<ModeSpec>,
+    /// The position in layout coordinates. If omitted, the output is placed
+    /// automatically.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub position: Option<(i32, i32)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scale: Option<f32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub transform: Option<ProfileTransform>,
+}
+
+#[cfg(feature = "serde")]
+fn default_enabled() -> bool {
+    true
+}
+
+/// A named arrangement of a specific set of outputs, in the style of kanshi.
+/// A profile applies only when each connected output matches exactly one of
+/// its entries, and each entry matches a connected output.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutputProfile {
+    pub name: String,
+    pub outputs: Vec<OutputProfileEntry>,
+}
+
+impl OutputProfile {
+    /// Matches the profile against a set of connected outputs. On success,
+    /// returns the total match score and, for each output, the index of the
+    /// entry that applies to it.
+    pub fn matches(&self, identities: &[OutputIdentity]) -> Option<(u32, Vec<usize>)> {
+        if identities.len() != self.outputs.len() {
+            return None;
+        }
+
+        let scores = identities
+            .iter()
+            .map(|identity| {
+                self.outputs
+                    .iter()
+                    .map(|entry| entry.matcher.score(identity))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut best = None;
+        let mut used = vec![false; self.outputs.len()];
+        best_assignment(&scores, &mut Vec::new(), &mut used, 0, &mut best);
+        best
+    }
+
+    /// Applies the profile to the outputs, and arranges them in the layout.
+    /// Every output is tested before anything is committed; if a commit or
+    /// the layout update fails anyway, the outputs and their positions in the
+    /// layout are restored.
+    pub fn apply(&self, outputs: &[Output], layout: &OutputLayout) -> Result<(), WlrError> {
+        let identities = outputs.iter().map(OutputIdentity::of).collect::<Vec<_>>();
+        let (_, assignment) = self
+            .matches(&identities)
+            .ok_or_else(|| WlrError::ProfileMismatch(self.name.clone()))?;
+
+        let states = outputs
+            .iter()
+            .zip(&assignment)
+            .map(|(output, &j)| entry_state(output, &self.outputs[j]))
+            .collect::<Vec<_>>();
+
+        for (output, state) in outputs.iter().zip(&states) {
+            if !output.test_state(state) {
+                return Err(WlrError::CallFailed("wlr_output_test_state".into()));
+            }
+        }
+
+        let previous = outputs.iter().map(current_state).collect::<Vec<_>>();
+        let placements = outputs
+            .iter()
+            .map(|output| placement(output, layout))
+            .collect::<Vec<_>>();
+
+        for (i, (output, state)) in outputs.iter().zip(&states).enumerate() {
+            if let Err(err) = output.commit_state(state) {
+                for (output, state) in outputs.iter().zip(&previous).take(i) {
+                    let _ = output.commit_state(state);
+                }
+
+                return Err(err);
+            }
+        }
+
+        for (output, &j) in outputs.iter().zip(&assignment) {
+            let entry = &self.outputs[j];
+            let placed = if !entry.enabled {
+                layout.remove(output);
+                Ok(())
+            } else if let Some((x, y)) = entry.position {
+                layout.add(output, x, y).map(|_| ())
+            } else {
+                layout.add_auto(output).map(|_| ())
+            };
+
+            if let Err(err) = placed {
+                for (output, state) in outputs.iter().zip(&previous) {
+                    let _ = output.commit_state(state);
+                }
+
+                for (output, placement) in outputs.iter().zip(&placements) {
+                    let _ = restore_placement(output, layout, *placement);
+                }
+
+                return Err(err);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Searches every way of giving each output its own entry, and keeps the one
+/// with the highest total score. Profiles only list a handful of outputs, so
+/// an exhaustive search is cheap, and unlike a greedy one it never misses a
+/// valid assignment.
+fn best_assignment(
+    scores: &[Vec<Option<u32>>],
+    assignment: &mut Vec<usize>,
+    used: &mut [bool],
+    total: u32,
+    best: &mut Option<(u32, Vec<usize>)>,
+) {
+    let Some(row) = scores.get(assignment.len()) else {
+        if best
+            .as_ref()
+            .is_none_or(|(best_total, _)| total > *best_total)
+        {
+            *best = Some((total, assignment.clone()));
+        }
+        return;
+    };
+
+    for (j, score) in row.iter().enumerate() {
+        let Some(score) = score else {
+            continue;
+        };
+        if used[j] {
+            continue;
+        }
+
+        used[j] = true;
+        assignment.push(j);
+        best_assignment(scores, assignment, used, total + score, best);
+        assignment.pop();
+        used[j] = false;
+    }
+}
+
+/// An ordered collection of profiles.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutputProfiles {
+    pub profiles: Vec<OutputProfile>,
+}
+
+impl OutputProfiles {
+    /// Finds the profile that matches the connected outputs most
+    /// specifically. Ties go to the profile listed first.
+    pub fn best_match(&self, identities: &[OutputIdentity]) -> Option<&OutputProfile> {
+        let mut best: Option<(u32, &OutputProfile)> = None;
+        for profile in &self.profiles {
+            if let Some((score, _)) = profile.matches(identities) {
+                if best.is_none_or(|(best_score, _)| score > best_score) {
+                    best = Some((score, profile));
+                }
+            }
+        }
+
+        best.map(|(_, profile)| profile)
+    }
+
+    /// Applies the best matching profile, if any. This should be called
+    /// whenever an output is connected or disconnected.
+    pub fn apply_best(
+        &self,
+        outputs: &[Output],
+        layout: &OutputLayout,
+    ) -> Result<Option<&OutputProfile>, WlrError> {
+        let identities = outputs.iter().map(OutputIdentity::of).collect::<Vec<_>>();
+        match self.best_match(&identities) {
+            Some(profile) => {
+                profile.apply(outputs, layout)?;
+                Ok(Some(profile))
+            }
+            None => Ok(None),
+        }
+    }
+}
+
+fn entry_state(output: &Output, entry: &OutputProfileEntry) -> OutputState {
+    let mut state = OutputState::new();
+    state.set_enabled(entry.enabled);
+    if !entry.enabled {
+        return state;
+    }
+
+    let mode = match entry.mode {
+        Some(spec) => output
+            .select_mode(spec.width, spec.height, spec.refresh, REFRESH_TOLERANCE)
+            .or(Some(ModeInfo {
+                width: spec.width,
+                height: spec.height,
+                refresh: spec.refresh.unwrap_or(0),
+                preferred: false,
+                picture_aspect_ratio: Default::default(),
+            })),
+        None if output.current_mode().is_none() => output.preferred_mode(),
+        None => None,
+    };
+
+    if let Some(mode) = mode {
+        state.set_mode(output, &mode);
+    }
+
+    if let Some(scale) = entry.scale {
+        state.set_scale(scale);
+    }
+
+    if let Some(transform) = entry.transform {
+        state.set_transform(transform.into());
+    }
+
+    state
+}
+
+/// Where an output is in the layout: None if it isn't in it, otherwise its
+/// position and whether it was placed automatically.
+type Placement = Option<((i32, i32), bool)>;
+
+fn placement(output: &Output, layout: &OutputLayout) -> Placement {
+    layout
+        .get(output)
+        .map(|l_output| (l_output.position(), l_output.auto_configured()))
+}
+
+fn restore_placement(
+    output: &Output,
+    layout: &OutputLayout,
+    placement: Placement,
+) -> Result<(), WlrError> {
+    match placement {
+        None => layout.remove(output),
+        Some((_, true)) => {
+            layout.add_auto(output)?;
+        }
+        Some(((x, y), false)) => {
+            layout.add(output, x, y)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn current_state(output: &Output) -> OutputState {
+    let mut state = OutputState::new();
+    state.set_enabled(output.enabled());
+    if let Some(mode) = output.current_mode() {
+        state.set_mode(output, &mode);
+    }
+
+    state.set_scale(output.scale());
+    state.set_transform(output.transform());
+    state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity(name: &str, make: &str, model: &str, serial: &str) -> OutputIdentity {
+        OutputIdentity {
+            name: name.into(),
+            make: Some(make.into()),
+            model: Some(model.into()),
+            serial: Some(serial.into()),
+        }
+    }
+
+    fn entry(matcher: OutputMatcher) -> OutputProfileEntry {
+        OutputProfileEntry {
+            matcher,
+            enabled: true,
+            mode: None,
+            position: None,
+            scale: None,
+            transform: None,
+        }
+    }
+
+    fn profile(name: &str, matchers: Vec<OutputMatcher>) -> OutputProfile {
+        OutputProfile {
+            name: name.into(),
+            outputs: matchers.into_iter().map(entry).collect(),
+        }
+    }
+
+    #[test]
+    fn matcher_score() {
+        let laptop = identity("eDP-1", "BOE", "0x0BCA", "0");
+
+        assert_eq!(OutputMatcher::default().score(&laptop), Some(0));
+
+        let by_name = OutputMatcher {
+            name: Some("eDP-1".into()),
+            ..Default::default()
+        };
+        assert_eq!(by_name.score(&laptop), Some(1));
+
+        let exact = OutputMatcher {
+            name: Some("eDP-1".into()),
+            make: Some("BOE".into()),
+            model: Some("0x0BCA".into()),
+            serial: Some("0".into()),
+        };
+        assert_eq!(exact.score(&laptop), Some(15));
+
+        let wrong_make = OutputMatcher {
+            make: Some("Dell".into()),
+            ..Default::default()
+        };
+        assert_eq!(wrong_make.score(&laptop), None);
+
+        let missing_serial = OutputMatcher {
+            serial: Some("0".into()),
+            ..Default::default()
+        };
+        assert_eq!(
+            missing_serial.score(&OutputIdentity {
+                name: "eDP-1".into(),
+                ..Default::default()
+            }),
+            None
+        );
+    }
+
+    #[test]
+    fn matches_needs_one_entry_per_output() {
+        let outputs = [identity("DP-1", "Dell", "U2720Q", "A")];
+        let profile = profile(
+            "two",
+            vec![OutputMatcher::default(), OutputMatcher::default()],
+        );
+
+        assert_eq!(profile.matches(&outputs), None);
+    }
+
+    #[test]
+    fn matches_finds_assignment_greedy_would_miss() {
+        let outputs = [
+            identity("DP-1", "Dell", "U2720Q", "A"),
+            identity("DP-2", "Dell", "U2720Q", "B"),
+        ];
+        // The first entry scores higher on DP-1, but only the second entry
+        // can take DP-1.
+        let profile = profile(
+            "desk",
+            vec![
+                OutputMatcher {
+                    make: Some("Dell".into()),
+                    ..Default::default()
+                },
+                OutputMatcher {
+                    name: Some("DP-1".into()),
+                    ..Default::default()
+                },
+            ],
+        );
+
+        assert_eq!(profile.matches(&outputs), Some((3, vec![1, 0])));
+    }
+
+    #[test]
+    fn matches_leaves_wildcards_for_the_rest() {
+        let outputs = [
+            identity("DP-1", "Dell", "U2720Q", "A"),
+            identity("DP-2", "LG", "27UK850", "B"),
+        ];
+        let profile = profile(
+            "desk",
+            vec![
+                OutputMatcher::default(),
+                OutputMatcher {
+                    serial: Some("A".into()),
+                    ..Default::default()
+                },
+            ],
+        );
+
+        assert_eq!(profile.matches(&outputs), Some((8, vec![1, 0])));
+    }
+
+    #[test]
+    fn best_match_picks_most_specific_profile() {
+        let outputs = [identity("DP-1", "Dell", "U2720Q", "A")];
+        let profiles = OutputProfiles {
+            profiles: vec![
+                profile("any", vec![OutputMatcher::default()]),
+                profile(
+                    "dell",
+                    vec![OutputMatcher {
+                        make: Some("Dell".into()),
+                        ..Default::default()
+                    }],
+                ),
+                profile(
+                    "lg",
+                    vec![OutputMatcher {
+                        make: Some("LG".into()),
+                        ..Default::default()
+                    }],
+                ),
+            ],
+        };
+
+        assert_eq!(profiles.best_match(&outputs).unwrap().name, "dell");
+        assert!(profiles.best_match(&[]).is_none());
+    }
+
+    #[test]
+    fn best_match_ties_go_to_first_profile() {
+        let outputs = [identity("DP-1", "Dell", "U2720Q", "A")];
+        let profiles = OutputProfiles {
+            profiles: vec![
+                profile("first", vec![OutputMatcher::default()]),
+                profile("second", vec![OutputMatcher::default()]),
+            ],
+        };
+
+        assert_eq!(profiles.best_match(&outputs).unwrap().name, "first");
+    }
+}
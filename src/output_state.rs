@@ -0,0 +1,310 @@
+use std::mem::MaybeUninit;
+
+use wayland_server::protocol::wl_output::Transform;
+use wlroots_sys::{
+    wl_output_transform, wlr_output_state,
+    wlr_output_state_field_WLR_OUTPUT_STATE_ADAPTIVE_SYNC_ENABLED,
+    wlr_output_state_field_WLR_OUTPUT_STATE_BUFFER, wlr_output_state_field_WLR_OUTPUT_STATE_DAMAGE,
+    wlr_output_state_field_WLR_OUTPUT_STATE_ENABLED,
+    wlr_output_state_field_WLR_OUTPUT_STATE_GAMMA_LUT,
+    wlr_output_state_field_WLR_OUTPUT_STATE_MODE,
+    wlr_output_state_field_WLR_OUTPUT_STATE_RENDER_FORMAT,
+    wlr_output_state_field_WLR_OUTPUT_STATE_SCALE,
+    wlr_output_state_field_WLR_OUTPUT_STATE_SUBPIXEL,
+    wlr_output_state_field_WLR_OUTPUT_STATE_TRANSFORM, wlr_output_state_finish,
+    wlr_output_state_init, wlr_output_state_mode_type_WLR_OUTPUT_STATE_MODE_CUSTOM,
+    wlr_output_state_set_adaptive_sync_enabled, wlr_output_state_set_custom_mode,
+    wlr_output_state_set_damage, wlr_output_state_set_enabled, wlr_output_state_set_gamma_lut,
+    wlr_output_state_set_mode, wlr_output_state_set_scale, wlr_output_state_set_transform,
+};
+
+use crate::{Buffer, ModeInfo, Output, Region, WlrError};
+
+/// A set of fields in an output state, mirroring `enum wlr_output_state_field`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct OutputStateFields(u32);
+
+impl OutputStateFields {
+    pub const BUFFER: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_BUFFER as u32);
+    pub const DAMAGE: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_DAMAGE as u32);
+    pub const MODE: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_MODE as u32);
+    pub const ENABLED: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_ENABLED as u32);
+    pub const SCALE: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_SCALE as u32);
+    pub const TRANSFORM: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_TRANSFORM as u32);
+    pub const ADAPTIVE_SYNC_ENABLED: Self =
+        Self(wlr_output_state_field_WLR_OUTPUT_STATE_ADAPTIVE_SYNC_ENABLED as u32);
+    pub const GAMMA_LUT: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_GAMMA_LUT as u32);
+    pub const RENDER_FORMAT: Self =
+        Self(wlr_output_state_field_WLR_OUTPUT_STATE_RENDER_FORMAT as u32);
+    pub const SUBPIXEL: Self = Self(wlr_output_state_field_WLR_OUTPUT_STATE_SUBPIXEL as u32);
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn intersects(self, other: Self) -> bool {
+        self.0 & other.0 != 0
+    }
+}
+
+impl std::ops::BitOr for OutputStateFields {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// A pending output configuration, equivalent to `struct wlr_output_state`.
+/// States are either owned by rust, in which case they're applied with
+/// [`Output::commit_state`](crate::Output::commit_state), or borrowed from
+/// wlroots for the duration of an event callback.
+#[repr(transparent)]
+pub struct OutputState(wlr_output_state);
+
+impl OutputState {
+    pub fn new() -> Self {
+        let mut state = MaybeUninit::<wlr_output_state>::uninit();
+        unsafe {
+            wlr_output_state_init(state.as_mut_ptr());
+            Self(state.assume_init())
+        }
+    }
+
+    /// Borrows a state owned by wlroots.
+    ///
+    /// # Safety
+    ///
+    /// The pointer must be valid for the lifetime `'a`.
+    pub(crate) unsafe fn from_ptr<'a>(ptr: *const wlr_output_state) -> &'a Self {
+        &*(ptr as *const Self)
+    }
+
+    pub fn committed(&self) -> OutputStateFields {
+        OutputStateFields(self.0.committed)
+    }
+
+    pub fn enabled(&self) -> Option<bool> {
+        self.committed()
+            .contains(OutputStateFields::ENABLED)
+            .then_some(self.0.enabled)
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        unsafe { wlr_output_state_set_enabled(self.as_mut_ptr(), enabled) }
+    }
+
+    /// The requested mode size and refresh rate (in mHz), if the state
+    /// changes the mode.
+    pub fn mode(&self) -> Option<(i32, i32, i32)> {
+        if !self.committed().contains(OutputStateFields::MODE) {
+            return None;
+        }
+
+        unsafe {
+            if self.0.mode_type == wlr_output_state_mode_type_WLR_OUTPUT_STATE_MODE_CUSTOM {
+                let m = &self.0.custom_mode;
+                Some((m.width, m.height, m.refresh))
+            } else {
+                self.0.mode.as_ref().map(|m| (m.width, m.height, m.refresh))
+            }
+        }
+    }
+
+    /// Sets the mode, using the output's matching advertised mode if there is
+    /// one, and a custom mode otherwise.
+    pub fn set_mode(&mut self, output: &Output, mode: &ModeInfo) {
+        unsafe {
+            match output.mode_ptr(mode) {
+                Some(m) => wlr_output_state_set_mode(self.as_mut_ptr(), m),
+                None => self.set_custom_mode(mode.width, mode.height, mode.refresh),
+            }
+        }
+    }
+
+    pub fn set_custom_mode(&mut self, width: i32, height: i32, refresh: i32) {
+        unsafe { wlr_output_state_set_custom_mode(self.as_mut_ptr(), width, height, refresh) }
+    }
+
+    pub fn scale(&self) -> Option<f32> {
+        self.committed()
+            .contains(OutputStateFields::SCALE)
+            .then_some(self.0.scale)
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        unsafe { wlr_output_state_set_scale(self.as_mut_ptr(), scale) }
+    }
+
+    pub fn transform(&self) -> Option<Transform> {
+        if !self.committed().contains(OutputStateFields::TRANSFORM) {
+            return None;
+        }
+
+        Transform::try_from(self.0.transform as u32).ok()
+    }
+
+    pub fn set_transform(&mut self, transform: Transform) {
+        unsafe {
+            wlr_output_state_set_transform(self.as_mut_ptr(), transform as wl_output_transform)
+        }
+    }
+
+    pub fn adaptive_sync_enabled(&self) -> Option<bool> {
+        self.committed()
+            .contains(OutputStateFields::ADAPTIVE_SYNC_ENABLED)
+            .then_some(self.0.adaptive_sync_enabled)
+    }
+
+    pub fn set_adaptive_sync_enabled(&mut self, enabled: bool) {
+        unsafe { wlr_output_state_set_adaptive_sync_enabled(self.as_mut_ptr(), enabled) }
+    }
+
+    /// The buffer to display, if the state includes one.
+    pub fn buffer(&self) -> Option<Buffer> {
+        if !self.committed().contains(OutputStateFields::BUFFER) || self.0.buffer.is_null() {
+            return None;
+        }
+
+        Some(Buffer::from_ptr(self.0.buffer))
+    }
+
+    /// Sets the damaged region of the buffer, in buffer-local coordinates.
+    pub fn set_damage(&mut self, damage: &Region) {
+        unsafe { wlr_output_state_set_damage(self.as_mut_ptr(), damage.as_ptr()) }
+    }
+
+    /// Sets the gamma ramps. Each ramp must have the same length, which
+    /// should match [`Output::gamma_size`].
+    pub fn set_gamma_lut(
+        &mut self,
+        red: &[u16],
+        green: &[u16],
+        blue: &[u16],
+    ) -> Result<(), WlrError> {
+        if red.len() != green.len() || red.len() != blue.len() {
+            return Err(WlrError::GammaSizeMismatch {
+                red: red.len(),
+                green: green.len(),
+                blue: blue.len(),
+            });
+        }
+
+        unsafe {
+            if !wlr_output_state_set_gamma_lut(
+                self.as_mut_ptr(),
+                red.len(),
+                red.as_ptr(),
+                green.as_ptr(),
+                blue.as_ptr(),
+            ) {
+                return Err(WlrError::CallFailed(
+                    "wlr_output_state_set_gamma_lut".into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Resets the gamma ramps to the identity.
+    pub fn reset_gamma_lut(&mut self) {
+        unsafe {
+            wlr_output_state_set_gamma_lut(
+                self.as_mut_ptr(),
+                0,
+                std::ptr::null(),
+                std::ptr::null(),
+                std::ptr::null(),
+            );
+        }
+    }
+
+    /// The gamma ramps in the state, as a single table of red, then green,
+    /// then blue values.
+    pub fn gamma_lut(&self) -> Option<&[u16]> {
+        if !self.committed().contains(OutputStateFields::GAMMA_LUT) || self.0.gamma_lut.is_null() {
+            return None;
+        }
+
+        unsafe {
+            Some(std::slice::from_raw_parts(
+                self.0.gamma_lut,
+                self.0.gamma_lut_size * 3,
+            ))
+        }
+    }
+
+    pub fn as_ptr(&self) -> *const wlr_output_state {
+        &self.0
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut wlr_output_state {
+        &mut self.0
+    }
+}
+
+impl Default for OutputState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for OutputState {
+    fn drop(&mut self) {
+        unsafe { wlr_output_state_finish(self.as_mut_ptr()) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamma_lut_round_trips() {
+        let red: Vec<u16> = (0..4).map(|i| i * 100).collect();
+        let green: Vec<u16> = (0..4).map(|i| i * 200).collect();
+        let blue: Vec<u16> = (0..4).map(|i| i * 300).collect();
+
+        let mut state = OutputState::new();
+        assert_eq!(state.gamma_lut(), None);
+
+        state.set_gamma_lut(&red, &green, &blue).unwrap();
+        assert!(state.committed().contains(OutputStateFields::GAMMA_LUT));
+        assert_eq!(
+            state.gamma_lut().unwrap(),
+            &[0, 100, 200, 300, 0, 200, 400, 600, 0, 300, 600, 900]
+        );
+    }
+
+    #[test]
+    fn gamma_lut_rejects_mismatched_ramps() {
+        let mut state = OutputState::new();
+        let err = state.set_gamma_lut(&[0; 4], &[0; 4], &[0; 3]).unwrap_err();
+        assert!(matches!(
+            err,
+            WlrError::GammaSizeMismatch {
+                red: 4,
+                green: 4,
+                blue: 3
+            }
+        ));
+        assert!(!state.committed().contains(OutputStateFields::GAMMA_LUT));
+    }
+
+    #[test]
+    fn reset_gamma_lut_commits_an_empty_lut() {
+        let mut state = OutputState::new();
+        state.reset_gamma_lut();
+        assert!(state.committed().contains(OutputStateFields::GAMMA_LUT));
+        assert_eq!(state.gamma_lut(), None);
+    }
+}
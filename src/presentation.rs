@@ -0,0 +1,34 @@
+use wayland_server::DisplayHandle;
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{wlr_presentation, wlr_presentation_create};
+
+use crate::{Backend, Destroyable, Handle, WlrError};
+
+/// Implements the presentation-time protocol, which tells clients exactly
+/// when their frames were displayed. Feedback is sent automatically for
+/// surfaces in a scene, once the manager is passed to
+/// [`Scene::set_presentation`](crate::Scene::set_presentation).
+pub struct Presentation(Box<Handle<wlr_presentation>>);
+
+impl Presentation {
+    pub fn new(display: &DisplayHandle, backend: &Backend) -> Result<Self, WlrError> {
+        let display = display.backend_handle().display_ptr();
+        let backend = backend.handle().try_as_ptr()?;
+        let ptr = unsafe { wlr_presentation_create(display, backend).as_mut() };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed("wlr_presentation_create".into())),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_presentation> {
+        &self.0
+    }
+}
+
+impl Destroyable for wlr_presentation {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
@@ -0,0 +1,155 @@
+use std::{cell::RefCell, rc::Rc};
+
+use wlroots_sys::{
+    wlr_output, wlr_output_add_software_cursors_to_render_pass, wlr_output_begin_render_pass,
+};
+
+use crate::{
+    DamageRing, Output, OutputState, OutputStateFields, Rect, Region, RenderPass, WlrError,
+};
+
+/// Drives damage-tracked rendering for a single output, without the scene
+/// graph. On each frame, the render callback is called with a render pass
+/// and the region of the buffer that must be repainted; the damage is then
+/// committed along with the buffer. Mode, scale and transform changes force a
+/// full repaint.
+///
+/// Rendering stops when the loop is dropped.
+pub struct OutputRenderLoop {
+    ring: Rc<RefCell<DamageRing>>,
+    // A wrapper of our own, separate from the caller's: the frame, commit and
+    // damage listeners are registered on it, so dropping the loop stops
+    // rendering even if the caller keeps their Output.
+    output: Output,
+}
+
+impl OutputRenderLoop {
+    pub fn attach(
+        output: &Output,
+        render: impl Fn(&mut RenderPass, &Region) + 'static,
+    ) -> Result<Self, WlrError> {
+        let ptr = output.handle().try_as_ptr()?;
+        let mut output = Output::from_ptr(ptr);
+
+        let ring = Rc::new(RefCell::new(DamageRing::new()));
+        {
+            let (width, height) = unsafe { ((*ptr).width, (*ptr).height) };
+            let mut ring = ring.borrow_mut();
+            ring.set_bounds(width, height);
+            ring.add_whole();
+        }
+
+        let frame_ring = ring.clone();
+        let frame_output = Output::from_ptr(ptr);
+        output.on_frame(move || {
+            if let Ok(output) = frame_output.handle().try_as_ptr() {
+                render_frame(output, &frame_output, &frame_ring, &render);
+            }
+        });
+
+        let commit_ring = ring.clone();
+        let commit_output = Output::from_ptr(ptr);
+        output.on_commit(move |event| {
+            let resized = OutputStateFields::MODE
+                | OutputStateFields::SCALE
+                | OutputStateFields::TRANSFORM
+                | OutputStateFields::ENABLED;
+            if event.committed.intersects(resized) {
+                let Ok(output) = commit_output.handle().try_as_ptr() else {
+                    return;
+                };
+
+                let (width, height) = unsafe { ((*output).width, (*output).height) };
+                let mut ring = commit_ring.borrow_mut();
+                ring.set_bounds(width, height);
+                ring.add_whole();
+            }
+        });
+
+        let damage_ring = ring.clone();
+        let damage_output = Output::from_ptr(ptr);
+        output.on_damage(move |event| {
+            if damage_ring.borrow_mut().add(&event.damage) {
+                schedule_frame(&damage_output);
+            }
+        });
+
+        let needs_frame_output = Output::from_ptr(ptr);
+        output.on_needs_frame(move || schedule_frame(&needs_frame_output));
+
+        schedule_frame(&output);
+        Ok(Self { ring, output })
+    }
+
+    /// Damages a rectangle, in buffer-local coordinates, and schedules a
+    /// frame.
+    pub fn add_damage(&self, rect: Rect) {
+        if self.ring.borrow_mut().add_rect(rect) {
+            schedule_frame(&self.output);
+        }
+    }
+
+    /// Damages a region, in buffer-local coordinates, and schedules a frame.
+    pub fn add_damage_region(&self, region: &Region) {
+        if self.ring.borrow_mut().add(region) {
+            schedule_frame(&self.output);
+        }
+    }
+
+    /// Forces a full repaint on the next frame.
+    pub fn damage_whole(&self) {
+        self.ring.borrow_mut().add_whole();
+        schedule_frame(&self.output);
+    }
+}
+
+fn schedule_frame(output: &Output) {
+    if output.handle().try_as_ptr().is_ok() {
+        output.schedule_frame();
+    }
+}
+
+fn render_frame(
+    ptr: *mut wlr_output,
+    output: &Output,
+    ring: &RefCell<DamageRing>,
+    render: &dyn Fn(&mut RenderPass, &Region),
+) {
+    if !ring.borrow().has_damage() {
+        return;
+    }
+
+    let mut state = OutputState::new();
+    let mut buffer_age = 0;
+    let pass = unsafe {
+        wlr_output_begin_render_pass(
+            ptr,
+            state.as_mut_ptr(),
+            &mut buffer_age,
+            std::ptr::null_mut(),
+        )
+    };
+    if pass.is_null() {
+        return;
+    }
+
+    // Don't hold the borrow while rendering, so that the callback can add
+    // damage for the next frame.
+    let buffer_damage = ring.borrow().buffer_damage(buffer_age);
+    let mut pass = RenderPass::from_ptr(pass);
+    (render)(&mut pass, &buffer_damage);
+
+    // Cursors that couldn't be put on a hardware plane are drawn on top.
+    unsafe {
+        wlr_output_add_software_cursors_to_render_pass(ptr, pass.as_ptr(), buffer_damage.as_ptr());
+    }
+    if pass.submit().is_err() {
+        return;
+    }
+
+    let frame_damage = ring.borrow().current();
+    state.set_damage(&frame_damage);
+    if output.commit_state(&state).is_ok() {
+        ring.borrow_mut().rotate();
+    }
+}
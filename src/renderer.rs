@@ -1,8 +1,19 @@
+use std::os::raw::c_void;
+
 use wayland_server::DisplayHandle;
 use wayland_sys::server::wl_signal;
-use wlroots_sys::{wlr_renderer, wlr_renderer_autocreate, wlr_renderer_init_wl_display};
+use wlroots_sys::{
+    wlr_pixman_renderer_create, wlr_render_blend_mode_WLR_RENDER_BLEND_MODE_PREMULTIPLIED,
+    wlr_render_color, wlr_render_pass, wlr_render_pass_add_rect, wlr_render_pass_submit,
+    wlr_render_rect_options, wlr_renderer, wlr_renderer_autocreate, wlr_renderer_init_wl_display,
+    wlr_texture_destroy, wlr_texture_from_buffer, wlr_texture_read_pixels,
+    wlr_texture_read_pixels_options,
+};
 
-use crate::{backend::Backend, Destroyable, Handle, WlrError};
+use crate::{
+    backend::Backend, Buffer, Destroyable, Handle, Image, Rect, Region, WlrError,
+    DRM_FORMAT_ABGR8888,
+};
 
 pub struct Renderer(Box<Handle<wlr_renderer>>);
 
@@ -17,6 +28,16 @@ impl Renderer {
         }
     }
 
+    /// Creates a software renderer, which works without a GPU.
+    pub fn pixman() -> Result<Renderer, WlrError> {
+        let ptr = unsafe { wlr_pixman_renderer_create().as_mut() };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed("wlr_pixman_renderer_create".into())),
+        }
+    }
+
     pub fn init_display(&self, display: &DisplayHandle) -> Result<(), WlrError> {
         let ptr = self.0.as_ptr();
 
@@ -31,6 +52,48 @@ impl Renderer {
         Ok(())
     }
 
+    /// Reads the contents of a buffer into CPU memory, in
+    /// [`DRM_FORMAT_ABGR8888`] (RGBA byte order).
+    pub fn read_pixels(&self, buffer: &Buffer) -> Result<Image, WlrError> {
+        let renderer = self.0.try_as_ptr()?;
+        let buffer = buffer.handle().try_as_ptr()?;
+
+        unsafe {
+            let texture = wlr_texture_from_buffer(renderer, buffer);
+            if texture.is_null() {
+                return Err(WlrError::CallFailed("wlr_texture_from_buffer".into()));
+            }
+
+            let (width, height) = ((*texture).width, (*texture).height);
+            let stride = width as usize * 4;
+            let mut data = vec![0u8; stride * height as usize];
+
+            let options = wlr_texture_read_pixels_options {
+                data: data.as_mut_ptr() as *mut c_void,
+                format: DRM_FORMAT_ABGR8888,
+                stride: stride as u32,
+                dst_x: 0,
+                dst_y: 0,
+                // An empty box reads the whole texture.
+                src_box: std::mem::zeroed(),
+            };
+
+            let ok = wlr_texture_read_pixels(texture, &options);
+            wlr_texture_destroy(texture);
+            if !ok {
+                return Err(WlrError::CallFailed("wlr_texture_read_pixels".into()));
+            }
+
+            Ok(Image {
+                width,
+                height,
+                format: DRM_FORMAT_ABGR8888,
+                stride,
+                data,
+            })
+        }
+    }
+
     pub fn handle(&self) -> &Handle<wlr_renderer> {
         &self.0
     }
@@ -41,3 +104,46 @@ impl Destroyable for wlr_renderer {
         &mut self.events.destroy
     }
 }
+
+/// An in-progress render pass, equivalent to `struct wlr_render_pass`. Passes
+/// are only valid until they're submitted, so they're only lent out to
+/// rendering callbacks.
+pub struct RenderPass(*mut wlr_render_pass);
+
+impl RenderPass {
+    pub(crate) fn from_ptr(ptr: *mut wlr_render_pass) -> Self {
+        Self(ptr)
+    }
+
+    /// Fills a rectangle with a premultiplied RGBA color, optionally clipped
+    /// to a region.
+    pub fn add_rect(&mut self, rect: Rect, color: [f32; 4], clip: Option<&Region>) {
+        let options = wlr_render_rect_options {
+            box_: rect.into(),
+            color: wlr_render_color {
+                r: color[0],
+                g: color[1],
+                b: color[2],
+                a: color[3],
+            },
+            clip: clip.map_or(std::ptr::null(), |c| c.as_ptr()),
+            blend_mode: wlr_render_blend_mode_WLR_RENDER_BLEND_MODE_PREMULTIPLIED,
+        };
+
+        unsafe { wlr_render_pass_add_rect(self.0, &options) }
+    }
+
+    pub fn as_ptr(&self) -> *mut wlr_render_pass {
+        self.0
+    }
+
+    pub(crate) fn submit(self) -> Result<(), WlrError> {
+        unsafe {
+            if !wlr_render_pass_submit(self.0) {
+                return Err(WlrError::CallFailed("wlr_render_pass_submit".into()));
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -1,33 +1,135 @@
-use libc::c_void;
-use wlroots_sys::{wlr_scene, wlr_scene_attach_output_layout, wlr_scene_create};
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{
+    wlr_scene, wlr_scene_attach_output_layout, wlr_scene_create, wlr_scene_get_scene_output,
+    wlr_scene_node_destroy, wlr_scene_output_create, wlr_scene_set_linux_dmabuf_v1,
+    wlr_scene_set_presentation,
+};
 
-use crate::{OutputLayout, WlrError};
+use crate::{
+    Destroyable, Handle, LinuxDmabuf, Output, OutputLayout, Presentation, SceneDump, SceneHit,
+    SceneOutput, SceneTree, WlrError,
+};
 
-pub struct Scene(*mut wlr_scene);
+/// The root of a scene graph, equivalent to `struct wlr_scene`. Dropping the
+/// scene destroys it through wlroots, along with all its nodes and scene
+/// outputs; any remaining wrappers for those report them as destroyed.
+pub struct Scene(Box<Handle<wlr_scene>>);
 
 impl Scene {
     pub fn new() -> Result<Self, WlrError> {
         let ptr = unsafe { wlr_scene_create().as_mut() };
         match ptr {
-            Some(layout) => Ok(Self(layout)),
+            Some(scene) => Ok(Self(Handle::new(scene))),
             None => Err(WlrError::CallFailed("wlr_scene_create".into())),
         }
     }
 
-    pub fn attach_output_layout(&self, output_layout: &OutputLayout) {
-        let output_layout = output_layout.handle().as_ptr();
+    pub fn handle(&self) -> &Handle<wlr_scene> {
+        &self.0
+    }
+
+    /// Keeps the positions of the scene's outputs in sync with the layout.
+    pub fn attach_output_layout(&self, output_layout: &OutputLayout) -> Result<(), WlrError> {
+        let scene = self.0.try_as_ptr()?;
+        let output_layout = output_layout.handle().try_as_ptr()?;
+
+        unsafe {
+            if wlr_scene_attach_output_layout(scene, output_layout).is_null() {
+                return Err(WlrError::CallFailed(
+                    "wlr_scene_attach_output_layout".into(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sends presentation feedback to surfaces in the scene when their frames
+    /// are displayed. The scene stops sending feedback if the manager is
+    /// destroyed, and a scene can only be given one manager.
+    pub fn set_presentation(&self, presentation: &Presentation) -> Result<(), WlrError> {
+        let scene = self.0.try_as_ptr()?;
+        let presentation = presentation.handle().try_as_ptr()?;
+
+        // wlroots asserts that the manager is only set once.
         unsafe {
-            wlr_scene_attach_output_layout(self.0, output_layout);
+            if !(*scene).presentation.is_null() {
+                return Err(WlrError::AlreadySet("scene presentation manager".into()));
+            }
+
+            wlr_scene_set_presentation(scene, presentation);
         }
+
+        Ok(())
+    }
+
+    /// Sends linux-dmabuf feedback to surfaces in the scene, based on the
+    /// outputs they're displayed on. The scene stops sending feedback if the
+    /// manager is destroyed, and a scene can only be given one manager.
+    pub fn set_linux_dmabuf_v1(&self, linux_dmabuf: &LinuxDmabuf) -> Result<(), WlrError> {
+        let scene = self.0.try_as_ptr()?;
+        let linux_dmabuf = linux_dmabuf.handle().try_as_ptr()?;
+
+        unsafe {
+            if !(*scene).linux_dmabuf_v1.is_null() {
+                return Err(WlrError::AlreadySet("scene linux-dmabuf manager".into()));
+            }
+
+            wlr_scene_set_linux_dmabuf_v1(scene, linux_dmabuf);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a view of the scene for an output. The scene output is
+    /// destroyed along with the output.
+    pub fn create_output(&self, output: &Output) -> Result<SceneOutput, WlrError> {
+        let scene = self.0.try_as_ptr()?;
+        let output = output.handle().try_as_ptr()?;
+        let ptr = unsafe { wlr_scene_output_create(scene, output).as_mut() };
+
+        match ptr {
+            Some(scene_output) => Ok(SceneOutput::from_ptr(scene_output)),
+            None => Err(WlrError::CallFailed("wlr_scene_output_create".into())),
+        }
+    }
+
+    /// The scene output for an output, if one has been created.
+    pub fn get_output(&self, output: &Output) -> Option<SceneOutput> {
+        let ptr = unsafe { wlr_scene_get_scene_output(self.0.as_ptr(), output.handle().as_ptr()) };
+        (!ptr.is_null()).then(|| SceneOutput::from_ptr(ptr))
+    }
+
+    /// Finds the topmost node at a point in layout coordinates.
+    pub fn node_at(&self, lx: f64, ly: f64) -> Option<SceneHit> {
+        self.tree().node().node_at(lx, ly)
+    }
+
+    /// The root of the scene graph.
+    pub fn tree(&self) -> SceneTree {
+        SceneTree::from_ptr(unsafe { &mut (*self.0.as_ptr()).tree })
+    }
+
+    /// Takes a snapshot of the whole scene graph, for debugging.
+    pub fn dump(&self) -> SceneDump {
+        SceneDump::of(&self.tree().node())
     }
 
     pub fn as_ptr(&self) -> *mut wlr_scene {
-        self.0
+        self.0.as_ptr()
     }
 }
 
 impl Drop for Scene {
     fn drop(&mut self) {
-        unsafe { libc::free(self.0 as *mut c_void) };
+        if let Ok(scene) = self.0.try_as_ptr() {
+            unsafe { wlr_scene_node_destroy(&mut (*scene).tree.node) }
+        }
+    }
+}
+
+impl Destroyable for wlr_scene {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.tree.node.events.destroy
     }
 }
@@ -0,0 +1,289 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    os::raw::{c_int, c_void},
+    rc::Rc,
+    time::Duration,
+};
+
+use libc::timespec;
+use wayland_server::protocol::wl_output::Transform;
+use wayland_sys::server::{
+    signal::{
+        rust_listener_create, rust_listener_destroy, rust_listener_get_user_data,
+        rust_listener_set_user_data, wl_signal_add,
+    },
+    wl_list_remove, wl_listener, wl_signal,
+};
+use wlroots_sys::{
+    wl_output_transform, wlr_fbox, wlr_scale_filter_mode_WLR_SCALE_FILTER_BILINEAR,
+    wlr_scale_filter_mode_WLR_SCALE_FILTER_NEAREST, wlr_scene_buffer, wlr_scene_buffer_create,
+    wlr_scene_buffer_set_buffer, wlr_scene_buffer_set_buffer_with_damage,
+    wlr_scene_buffer_set_dest_size, wlr_scene_buffer_set_filter_mode, wlr_scene_buffer_set_opacity,
+    wlr_scene_buffer_set_source_box, wlr_scene_buffer_set_transform, wlr_scene_output,
+    wlr_scene_surface_try_from_buffer,
+};
+
+use crate::{
+    output::duration_from_timespec, Buffer, Destroyable, FRect, Handle, Region, SceneNode,
+    SceneOutput, SceneTree, Surface, WlrError,
+};
+
+/// How a buffer is sampled when it's scaled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum FilterMode {
+    #[default]
+    Bilinear,
+    Nearest,
+}
+
+/// A node displaying a buffer, equivalent to `struct wlr_scene_buffer`. The
+/// node keeps its own lock on the buffer it displays.
+pub struct SceneBuffer(Box<Handle<wlr_scene_buffer>>);
+
+impl SceneBuffer {
+    /// Creates a buffer node. If `buffer` is None, the node displays nothing
+    /// until a buffer is set.
+    pub fn new(parent: &SceneTree, buffer: Option<&Buffer>) -> Result<Self, WlrError> {
+        let parent = parent.handle().try_as_ptr()?;
+        let buffer = match buffer {
+            Some(b) => b.handle().try_as_ptr()?,
+            None => std::ptr::null_mut(),
+        };
+
+        let ptr = unsafe { wlr_scene_buffer_create(parent, buffer).as_mut() };
+        match ptr {
+            Some(scene_buffer) => Ok(Self(Handle::new(scene_buffer))),
+            None => Err(WlrError::CallFailed("wlr_scene_buffer_create".into())),
+        }
+    }
+
+    pub fn from_ptr(ptr: *mut wlr_scene_buffer) -> Self {
+        Self(Handle::new(ptr))
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_scene_buffer> {
+        &self.0
+    }
+
+    pub fn node(&self) -> SceneNode {
+        SceneNode::from_ptr(unsafe { &mut (*self.0.as_ptr()).node })
+    }
+
+    /// The buffer currently displayed, if any.
+    pub fn buffer(&self) -> Option<Buffer> {
+        let buffer = unsafe { (*self.0.as_ptr()).buffer };
+        (!buffer.is_null()).then(|| Buffer::from_ptr(buffer))
+    }
+
+    /// The size of the node in layout coordinates: the destination size if
+    /// one is set, and the (transformed) buffer size otherwise.
+    pub fn size(&self) -> (i32, i32) {
+        unsafe {
+            let p = self.0.as_ptr();
+            if (*p).dst_width > 0 && (*p).dst_height > 0 {
+                return ((*p).dst_width, (*p).dst_height);
+            }
+
+            match (*p).buffer.as_ref() {
+                // Odd transforms rotate the buffer by 90 or 270 degrees.
+                Some(b) if (*p).transform & 1 != 0 => (b.height, b.width),
+                Some(b) => (b.width, b.height),
+                None => (0, 0),
+            }
+        }
+    }
+
+    /// The client surface displayed by the node, if it was created for one.
+    pub fn surface(&self) -> Option<Surface> {
+        unsafe {
+            let scene_surface = wlr_scene_surface_try_from_buffer(self.0.as_ptr());
+            scene_surface
+                .as_ref()
+                .map(|scene_surface| Surface::from_ptr(scene_surface.surface))
+        }
+    }
+
+    /// Replaces the buffer, damaging the whole node.
+    pub fn set_buffer(&self, buffer: Option<&Buffer>) -> Result<(), WlrError> {
+        let buffer = match buffer {
+            Some(b) => b.handle().try_as_ptr()?,
+            None => std::ptr::null_mut(),
+        };
+
+        unsafe { wlr_scene_buffer_set_buffer(self.0.try_as_ptr()?, buffer) };
+        Ok(())
+    }
+
+    /// Replaces the buffer, damaging only the given region, in buffer
+    /// coordinates.
+    pub fn set_buffer_with_damage(&self, buffer: &Buffer, damage: &Region) -> Result<(), WlrError> {
+        let buffer = buffer.handle().try_as_ptr()?;
+        unsafe {
+            wlr_scene_buffer_set_buffer_with_damage(self.0.try_as_ptr()?, buffer, damage.as_ptr())
+        };
+
+        Ok(())
+    }
+
+    /// Crops the buffer to a box, in buffer coordinates. None displays the
+    /// whole buffer.
+    pub fn set_source_box(&self, source: Option<FRect>) {
+        let source = source.map(wlr_fbox::from);
+        let ptr = source.as_ref().map_or(std::ptr::null(), |b| b as *const _);
+        unsafe { wlr_scene_buffer_set_source_box(self.0.as_ptr(), ptr) }
+    }
+
+    /// Scales the buffer to a size, in layout coordinates. A size of (0, 0)
+    /// uses the buffer's own size.
+    pub fn set_dest_size(&self, width: i32, height: i32) {
+        unsafe { wlr_scene_buffer_set_dest_size(self.0.as_ptr(), width, height) }
+    }
+
+    pub fn set_transform(&self, transform: Transform) {
+        unsafe { wlr_scene_buffer_set_transform(self.0.as_ptr(), transform as wl_output_transform) }
+    }
+
+    pub fn opacity(&self) -> f32 {
+        unsafe { (*self.0.as_ptr()).opacity }
+    }
+
+    pub fn set_opacity(&self, opacity: f32) {
+        unsafe { wlr_scene_buffer_set_opacity(self.0.as_ptr(), opacity.clamp(0.0, 1.0)) }
+    }
+
+    pub fn set_filter_mode(&self, filter_mode: FilterMode) {
+        let filter_mode = match filter_mode {
+            FilterMode::Bilinear => wlr_scale_filter_mode_WLR_SCALE_FILTER_BILINEAR,
+            FilterMode::Nearest => wlr_scale_filter_mode_WLR_SCALE_FILTER_NEAREST,
+        };
+
+        unsafe { wlr_scene_buffer_set_filter_mode(self.0.as_ptr(), filter_mode) }
+    }
+
+    /// Overrides which points of the node accept input, for hit testing. The
+    /// callback gets a point relative to the node, and by default every
+    /// point inside the node accepts input. The callback is dropped along
+    /// with the node, or when it's replaced or cleared.
+    ///
+    /// Nodes displaying a surface already use the surface's input region,
+    /// and can't be overridden.
+    pub fn set_point_accepts_input(
+        &self,
+        accepts_input: impl Fn(i32, i32) -> bool + 'static,
+    ) -> Result<(), WlrError> {
+        let buffer = self.take_point_accepts_input()?;
+
+        let listener = rust_listener_create(point_accepts_input_destroy);
+        unsafe {
+            rust_listener_set_user_data(listener, buffer as *mut c_void);
+            wl_signal_add(&mut (*buffer).node.events.destroy, listener);
+            (*buffer).point_accepts_input = Some(point_accepts_input);
+        }
+
+        let callback = PointAcceptsInput {
+            callback: Rc::new(accepts_input),
+            destroy_listener: listener,
+        };
+        POINT_ACCEPTS_INPUT.with(|callbacks| callbacks.borrow_mut().insert(buffer, callback));
+
+        Ok(())
+    }
+
+    /// Removes the callback set with [`SceneBuffer::set_point_accepts_input`],
+    /// so that every point inside the node accepts input again.
+    pub fn clear_point_accepts_input(&self) -> Result<(), WlrError> {
+        self.take_point_accepts_input()?;
+        Ok(())
+    }
+
+    /// Drops the node's callback, if any, along with its destroy listener,
+    /// and returns the node for a new one to be set.
+    fn take_point_accepts_input(&self) -> Result<*mut wlr_scene_buffer, WlrError> {
+        let buffer = self.0.try_as_ptr()?;
+        if self.surface().is_some() {
+            return Err(WlrError::SurfaceNode);
+        }
+
+        POINT_ACCEPTS_INPUT.with(|callbacks| callbacks.borrow_mut().remove(&buffer));
+        unsafe { (*buffer).point_accepts_input = None };
+        Ok(buffer)
+    }
+
+    /// Called when the node becomes visible on an output. Surface nodes use
+    /// this to send `wl_surface.enter`.
+    pub fn on_output_enter(&mut self, cb: impl Fn(SceneOutput) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.output_enter };
+        self.0.add_listener(signal, move |data| {
+            (cb)(SceneOutput::from_ptr(data as *mut wlr_scene_output));
+        });
+    }
+
+    /// Called when the node is no longer visible on an output.
+    pub fn on_output_leave(&mut self, cb: impl Fn(SceneOutput) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.output_leave };
+        self.0.add_listener(signal, move |data| {
+            (cb)(SceneOutput::from_ptr(data as *mut wlr_scene_output));
+        });
+    }
+
+    /// Called when a frame containing the node has been rendered, with a
+    /// timestamp on the monotonic clock; see
+    /// [`SceneOutput::send_frame_done`].
+    pub fn on_frame_done(&mut self, cb: impl Fn(Duration) + 'static) {
+        let signal = unsafe { &mut (*self.handle().as_ptr()).events.frame_done };
+        self.0.add_listener(signal, move |data| {
+            let when = unsafe { &*(data as *const timespec) };
+            (cb)(duration_from_timespec(when));
+        });
+    }
+}
+
+/// A rust callback for `wlr_scene_buffer.point_accepts_input`.
+struct PointAcceptsInput {
+    callback: Rc<dyn Fn(i32, i32) -> bool>,
+    destroy_listener: *mut wl_listener,
+}
+
+impl Drop for PointAcceptsInput {
+    fn drop(&mut self) {
+        unsafe {
+            wl_list_remove(&mut (*self.destroy_listener).link);
+            rust_listener_destroy(self.destroy_listener);
+        }
+    }
+}
+
+thread_local! {
+    // wlroots calls back with just the buffer and no user data, so this is
+    // the only way back to the closure. Hit tests run on the thread that
+    // owns the scene, which is the thread that set the callback.
+    static POINT_ACCEPTS_INPUT: RefCell<HashMap<*mut wlr_scene_buffer, PointAcceptsInput>> =
+        RefCell::new(HashMap::new());
+}
+
+unsafe extern "C" fn point_accepts_input(
+    buffer: *mut wlr_scene_buffer,
+    sx: c_int,
+    sy: c_int,
+) -> bool {
+    // Release the map before calling out, in case the callback changes it.
+    let callback = POINT_ACCEPTS_INPUT
+        .with(|callbacks| callbacks.borrow().get(&buffer).map(|c| c.callback.clone()));
+
+    match callback {
+        Some(callback) => callback(sx, sy),
+        None => true,
+    }
+}
+
+unsafe extern "C" fn point_accepts_input_destroy(listener: *mut wl_listener, _data: *mut c_void) {
+    let buffer = rust_listener_get_user_data(listener) as *mut wlr_scene_buffer;
+    POINT_ACCEPTS_INPUT.with(|callbacks| callbacks.borrow_mut().remove(&buffer));
+}
+
+impl Destroyable for wlr_scene_buffer {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.node.events.destroy
+    }
+}
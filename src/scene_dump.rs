@@ -0,0 +1,326 @@
+use std::fmt::Write;
+
+use crate::{SceneNode, SceneNodeType};
+
+/// A snapshot of a scene node and its descendants, for debugging and
+/// snapshot tests. Snapshots can be rendered as indented text, JSON or a
+/// Graphviz digraph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneDump {
+    pub node_type: SceneNodeType,
+    /// The position of the node, relative to its parent.
+    pub x: i32,
+    pub y: i32,
+    pub enabled: bool,
+    /// The size of rectangle and buffer nodes, in layout coordinates.
+    pub size: Option<(i32, i32)>,
+    /// The color of rectangle nodes.
+    pub color: Option<[f32; 4]>,
+    /// The protocol id of the surface displayed by a buffer node.
+    pub surface: Option<u32>,
+    /// A caller-provided label; see [`SceneDump::with_labels`].
+    pub label: Option<String>,
+    pub children: Vec<SceneDump>,
+}
+
+impl SceneDump {
+    pub fn of(node: &SceneNode) -> Self {
+        Self::with_labels(node, &|_| None)
+    }
+
+    /// Takes a snapshot, labelling each node with the result of `labeler`;
+    /// for example, a window title looked up from the node's data.
+    pub fn with_labels(node: &SceneNode, labeler: &dyn Fn(&SceneNode) -> Option<String>) -> Self {
+        let (x, y) = node.position();
+        let mut dump = Self {
+            node_type: node.node_type(),
+            x,
+            y,
+            enabled: node.enabled(),
+            size: None,
+            color: None,
+            surface: None,
+            label: labeler(node),
+            children: Vec::new(),
+        };
+
+        if let Some(tree) = node.as_tree() {
+            dump.children = tree
+                .children()
+                .iter()
+                .map(|child| Self::with_labels(child, labeler))
+                .collect();
+        } else if let Some(rect) = node.as_rect() {
+            dump.size = Some(rect.size());
+            dump.color = Some(rect.color());
+        } else if let Some(buffer) = node.as_buffer() {
+            dump.size = Some(buffer.size());
+            dump.surface = buffer.surface().map(|surface| surface.id());
+        }
+
+        dump
+    }
+
+    /// Renders the snapshot as an indented tree, one node per line.
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        self.write_text(&mut out, 0);
+        out
+    }
+
+    fn write_text(&self, out: &mut String, depth: usize) {
+        let _ = write!(out, "{:1$}", "", depth * 2);
+        out.push_str(&self.summary());
+        if let Some(label) = &self.label {
+            let _ = write!(out, " {:?}", label);
+        }
+        out.push('\n');
+
+        for child in &self.children {
+            child.write_text(out, depth + 1);
+        }
+    }
+
+    /// Renders the snapshot as a JSON object. Children are nested in a
+    /// `children` array, from bottom to top. Color channels that aren't
+    /// finite are written as `null`.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out);
+        out
+    }
+
+    fn write_json(&self, out: &mut String) {
+        let _ = write!(
+            out,
+            "{{\"type\":\"{}\",\"x\":{},\"y\":{},\"enabled\":{}",
+            type_name(self.node_type),
+            self.x,
+            self.y,
+            self.enabled
+        );
+
+        if let Some((width, height)) = self.size {
+            let _ = write!(out, ",\"width\":{},\"height\":{}", width, height);
+        }
+        if let Some(color) = self.color {
+            out.push_str(",\"color\":[");
+            for (i, channel) in color.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_json_number(out, channel);
+            }
+            out.push(']');
+        }
+        if let Some(surface) = self.surface {
+            let _ = write!(out, ",\"surface\":{}", surface);
+        }
+        if let Some(label) = &self.label {
+            out.push_str(",\"label\":");
+            write_json_string(out, label);
+        }
+
+        out.push_str(",\"children\":[");
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            child.write_json(out);
+        }
+        out.push_str("]}");
+    }
+
+    /// Renders the snapshot as a Graphviz digraph. Disabled nodes are drawn
+    /// dashed.
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph scene {\n  node [shape=box];\n");
+        self.write_dot(&mut out, &mut 0);
+        out.push_str("}\n");
+        out
+    }
+
+    /// Writes the node and its subtree, returning the node's id.
+    fn write_dot(&self, out: &mut String, next_id: &mut usize) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        let mut label = self.summary();
+        if let Some(l) = &self.label {
+            label.push('\n');
+            label.push_str(l);
+        }
+
+        let _ = write!(out, "  n{} [label=", id);
+        write_json_string(out, &label);
+        if !self.enabled {
+            out.push_str(", style=dashed");
+        }
+        out.push_str("];\n");
+
+        for child in &self.children {
+            let child_id = child.write_dot(out, next_id);
+            let _ = writeln!(out, "  n{} -> n{};", id, child_id);
+        }
+
+        id
+    }
+
+    /// A one-line description of the node itself, without its label.
+    fn summary(&self) -> String {
+        let mut s = format!("{} ({}, {})", type_name(self.node_type), self.x, self.y);
+        if let Some((width, height)) = self.size {
+            let _ = write!(s, " {}x{}", width, height);
+        }
+        if let Some(color) = self.color {
+            let _ = write!(s, " color={:?}", color);
+        }
+        if let Some(surface) = self.surface {
+            let _ = write!(s, " wl_surface@{}", surface);
+        }
+        if !self.enabled {
+            s.push_str(" disabled");
+        }
+        s
+    }
+}
+
+fn type_name(node_type: SceneNodeType) -> &'static str {
+    match node_type {
+        SceneNodeType::Tree => "tree",
+        SceneNodeType::Rect => "rect",
+        SceneNodeType::Buffer => "buffer",
+    }
+}
+
+/// Writes a number, or `null` for NaN and infinities, which JSON can't
+/// represent.
+fn write_json_number(out: &mut String, n: f32) {
+    if n.is_finite() {
+        let _ = write!(out, "{}", n);
+    } else {
+        out.push_str("null");
+    }
+}
+
+/// Writes a quoted, escaped string. The escapes are valid in both JSON and
+/// DOT, where `\n` is a centered line break.
+fn write_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(node_type: SceneNodeType, x: i32, y: i32) -> SceneDump {
+        SceneDump {
+            node_type,
+            x,
+            y,
+            enabled: true,
+            size: None,
+            color: None,
+            surface: None,
+            label: None,
+            children: Vec::new(),
+        }
+    }
+
+    fn sample() -> SceneDump {
+        let rect = SceneDump {
+            size: Some((5, 5)),
+            color: Some([1.0, 0.0, 0.0, 1.0]),
+            label: Some("bg".into()),
+            ..leaf(SceneNodeType::Rect, 10, 20)
+        };
+        let buffer = SceneDump {
+            enabled: false,
+            size: Some((64, 32)),
+            surface: Some(3),
+            ..leaf(SceneNodeType::Buffer, 0, 0)
+        };
+
+        SceneDump {
+            label: Some("a \"b\"\nc".into()),
+            children: vec![rect, buffer],
+            ..leaf(SceneNodeType::Tree, 0, 0)
+        }
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(
+            sample().to_text(),
+            r#"tree (0, 0) "a \"b\"\nc"
+  rect (10, 20) 5x5 color=[1.0, 0.0, 0.0, 1.0] "bg"
+  buffer (0, 0) 64x32 wl_surface@3 disabled
+"#
+        );
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            sample().to_json(),
+            concat!(
+                r#"{"type":"tree","x":0,"y":0,"enabled":true,"label":"a \"b\"\nc","children":["#,
+                r#"{"type":"rect","x":10,"y":20,"enabled":true,"width":5,"height":5,"#,
+                r#""color":[1,0,0,1],"label":"bg","children":[]},"#,
+                r#"{"type":"buffer","x":0,"y":0,"enabled":false,"width":64,"height":32,"#,
+                r#""surface":3,"children":[]}]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn json_non_finite_colors() {
+        let rect = SceneDump {
+            size: Some((1, 1)),
+            color: Some([f32::NAN, f32::INFINITY, f32::NEG_INFINITY, 0.5]),
+            ..leaf(SceneNodeType::Rect, 0, 0)
+        };
+        assert_eq!(
+            rect.to_json(),
+            concat!(
+                r#"{"type":"rect","x":0,"y":0,"enabled":true,"width":1,"height":1,"#,
+                r#""color":[null,null,null,0.5],"children":[]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn dot() {
+        assert_eq!(
+            sample().to_dot(),
+            r#"digraph scene {
+  node [shape=box];
+  n0 [label="tree (0, 0)\na \"b\"\nc"];
+  n1 [label="rect (10, 20) 5x5 color=[1.0, 0.0, 0.0, 1.0]\nbg"];
+  n0 -> n1;
+  n2 [label="buffer (0, 0) 64x32 wl_surface@3 disabled", style=dashed];
+  n0 -> n2;
+}
+"#
+        );
+    }
+
+    #[test]
+    fn control_characters_are_escaped() {
+        let mut out = String::new();
+        write_json_string(&mut out, "tab\there\\");
+        assert_eq!(out, r#""tab\u0009here\\""#);
+    }
+}
@@ -0,0 +1,558 @@
+use std::{any::Any, cell::RefCell, collections::HashMap, os::raw::c_void, rc::Rc};
+
+use wayland_sys::{
+    common::wl_list,
+    server::{
+        signal::{
+            rust_listener_create, rust_listener_destroy, rust_listener_get_user_data,
+            rust_listener_set_user_data, wl_signal_add,
+        },
+        wl_list_remove, wl_listener, wl_signal,
+    },
+};
+use wlroots_sys::{
+    wlr_output_effective_resolution, wlr_scene, wlr_scene_buffer, wlr_scene_buffer_from_node,
+    wlr_scene_node, wlr_scene_node_at, wlr_scene_node_coords, wlr_scene_node_destroy,
+    wlr_scene_node_lower_to_bottom, wlr_scene_node_place_above, wlr_scene_node_place_below,
+    wlr_scene_node_raise_to_top, wlr_scene_node_reparent, wlr_scene_node_set_enabled,
+    wlr_scene_node_set_position, wlr_scene_node_type_WLR_SCENE_NODE_BUFFER,
+    wlr_scene_node_type_WLR_SCENE_NODE_RECT, wlr_scene_node_type_WLR_SCENE_NODE_TREE,
+    wlr_scene_output, wlr_scene_rect_from_node, wlr_scene_subsurface_tree_create,
+    wlr_scene_surface_create, wlr_scene_tree, wlr_scene_tree_create, wlr_scene_tree_from_node,
+    wlr_scene_xdg_surface_create,
+};
+
+use crate::{
+    macros::*, Destroyable, Handle, Output, Rect, SceneBuffer, SceneRect, Surface, WlrError,
+    XdgSurface,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SceneNodeType {
+    Tree,
+    Rect,
+    Buffer,
+}
+
+/// A node in the scene graph, equivalent to `struct wlr_scene_node`. Nodes
+/// are owned by their parent tree; dropping the wrapper doesn't destroy the
+/// node, but [`SceneNode::destroy`] does.
+pub struct SceneNode(Box<Handle<wlr_scene_node>>);
+
+impl SceneNode {
+    pub fn from_ptr(ptr: *mut wlr_scene_node) -> Self {
+        Self(Handle::new(ptr))
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_scene_node> {
+        &self.0
+    }
+
+    pub fn node_type(&self) -> SceneNodeType {
+        match unsafe { (*self.0.as_ptr()).type_ } {
+            wlr_scene_node_type_WLR_SCENE_NODE_TREE => SceneNodeType::Tree,
+            wlr_scene_node_type_WLR_SCENE_NODE_RECT => SceneNodeType::Rect,
+            _ => SceneNodeType::Buffer,
+        }
+    }
+
+    /// Returns the node as a tree, if it is one.
+    pub fn as_tree(&self) -> Option<SceneTree> {
+        if self.node_type() != SceneNodeType::Tree {
+            return None;
+        }
+
+        let tree = unsafe { wlr_scene_tree_from_node(self.0.as_ptr()) };
+        Some(SceneTree::from_ptr(tree))
+    }
+
+    /// Returns the node as a rectangle, if it is one.
+    pub fn as_rect(&self) -> Option<SceneRect> {
+        if self.node_type() != SceneNodeType::Rect {
+            return None;
+        }
+
+        let rect = unsafe { wlr_scene_rect_from_node(self.0.as_ptr()) };
+        Some(SceneRect::from_ptr(rect))
+    }
+
+    /// Returns the node as a buffer node, if it is one.
+    pub fn as_buffer(&self) -> Option<SceneBuffer> {
+        if self.node_type() != SceneNodeType::Buffer {
+            return None;
+        }
+
+        let buffer = unsafe { wlr_scene_buffer_from_node(self.0.as_ptr()) };
+        Some(SceneBuffer::from_ptr(buffer))
+    }
+
+    /// The tree containing the node, or None for the root of the scene.
+    pub fn parent(&self) -> Option<SceneTree> {
+        let parent = unsafe { (*self.0.as_ptr()).parent };
+        (!parent.is_null()).then(|| SceneTree::from_ptr(parent))
+    }
+
+    /// The position of the node, relative to its parent.
+    pub fn position(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).x, (*p).y) }
+    }
+
+    pub fn set_position(&self, x: i32, y: i32) {
+        unsafe { wlr_scene_node_set_position(self.0.as_ptr(), x, y) }
+    }
+
+    /// Whether the node itself is enabled. A node is only displayed if it
+    /// and all its ancestors are enabled.
+    pub fn enabled(&self) -> bool {
+        unsafe { (*self.0.as_ptr()).enabled }
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        unsafe { wlr_scene_node_set_enabled(self.0.as_ptr(), enabled) }
+    }
+
+    /// The position of the node in scene (layout) coordinates.
+    pub fn coords(&self) -> (i32, i32) {
+        self.coords_and_visibility().0
+    }
+
+    /// Returns true if the node and all its ancestors are enabled.
+    pub fn enabled_in_tree(&self) -> bool {
+        self.coords_and_visibility().1
+    }
+
+    fn coords_and_visibility(&self) -> ((i32, i32), bool) {
+        let (mut lx, mut ly) = (0, 0);
+        let enabled = unsafe { wlr_scene_node_coords(self.0.as_ptr(), &mut lx, &mut ly) };
+        ((lx, ly), enabled)
+    }
+
+    /// The outputs that the node, or for a tree any of its descendants,
+    /// currently overlaps. Rectangles are checked against the outputs'
+    /// viewports; buffers use the outputs wlroots considers them to be on,
+    /// which agree with [`SceneBuffer::on_output_enter`] and
+    /// [`SceneBuffer::on_output_leave`]. Disabled nodes don't overlap any
+    /// outputs.
+    pub fn outputs(&self) -> Vec<Output> {
+        let mut outputs = Vec::new();
+        if !self.enabled_in_tree() {
+            return outputs;
+        }
+
+        let (lx, ly) = self.coords();
+        let mut boxes = Vec::new();
+        let mut buffers = Vec::new();
+        collect_visible(self, lx, ly, &mut boxes, &mut buffers);
+
+        unsafe {
+            // The root of the graph is the tree embedded in the scene.
+            let mut root = self.0.as_ptr();
+            while !(*root).parent.is_null() {
+                root = &mut (*(*root).parent).node;
+            }
+            let tree = container_of!(root, wlr_scene_tree, node);
+            let scene = container_of!(tree, wlr_scene, tree);
+
+            let head = &mut (*scene).outputs as *mut wl_list;
+            list_for_each!(scene_output, head, wlr_scene_output, link, {
+                let output = (*scene_output).output;
+                let mask = 1u64 << (*scene_output).index;
+                let on_buffer = buffers
+                    .iter()
+                    .any(|&buffer| (*buffer).active_outputs & mask != 0);
+
+                let on_rect = (*output).enabled && {
+                    let (mut width, mut height) = (0, 0);
+                    wlr_output_effective_resolution(output, &mut width, &mut height);
+
+                    let viewport = Rect::new((*scene_output).x, (*scene_output).y, width, height);
+                    boxes.iter().any(|b| b.intersects(&viewport))
+                };
+
+                if on_buffer || on_rect {
+                    outputs.push(Output::from_ptr(output));
+                }
+            });
+        }
+
+        outputs
+    }
+
+    /// Moves the node above all its siblings.
+    pub fn raise_to_top(&self) {
+        unsafe { wlr_scene_node_raise_to_top(self.0.as_ptr()) }
+    }
+
+    /// Moves the node below all its siblings.
+    pub fn lower_to_bottom(&self) {
+        unsafe { wlr_scene_node_lower_to_bottom(self.0.as_ptr()) }
+    }
+
+    /// Moves the node directly above a sibling.
+    pub fn place_above(&self, sibling: &SceneNode) -> Result<(), WlrError> {
+        let (node, sibling) = self.sibling_ptrs(sibling)?;
+        unsafe { wlr_scene_node_place_above(node, sibling) };
+        Ok(())
+    }
+
+    /// Moves the node directly below a sibling.
+    pub fn place_below(&self, sibling: &SceneNode) -> Result<(), WlrError> {
+        let (node, sibling) = self.sibling_ptrs(sibling)?;
+        unsafe { wlr_scene_node_place_below(node, sibling) };
+        Ok(())
+    }
+
+    fn sibling_ptrs(
+        &self,
+        sibling: &SceneNode,
+    ) -> Result<(*mut wlr_scene_node, *mut wlr_scene_node), WlrError> {
+        let node = self.0.try_as_ptr()?;
+        let sibling = sibling.0.try_as_ptr()?;
+
+        // wlroots asserts on these, so check them up front.
+        unsafe {
+            if node == sibling || (*node).parent != (*sibling).parent {
+                return Err(WlrError::NotSiblings);
+            }
+        }
+
+        Ok((node, sibling))
+    }
+
+    /// Moves the node into another tree, keeping its relative position. The
+    /// new parent can't be the node itself or one of its descendants.
+    pub fn reparent(&self, new_parent: &SceneTree) -> Result<(), WlrError> {
+        let node = self.0.try_as_ptr()?;
+        let new_parent = new_parent.handle().try_as_ptr()?;
+
+        unsafe {
+            let mut ancestor = new_parent;
+            while !ancestor.is_null() {
+                if &mut (*ancestor).node as *mut _ == node {
+                    return Err(WlrError::ReparentCycle);
+                }
+                ancestor = (*ancestor).node.parent;
+            }
+
+            wlr_scene_node_reparent(node, new_parent);
+        }
+
+        Ok(())
+    }
+
+    /// Finds the topmost node at a point in layout coordinates, searching
+    /// this node and its descendants. Only nodes that accept input are
+    /// considered.
+    pub fn node_at(&self, lx: f64, ly: f64) -> Option<SceneHit> {
+        let (mut sx, mut sy) = (0.0, 0.0);
+        let node = unsafe { wlr_scene_node_at(self.0.as_ptr(), lx, ly, &mut sx, &mut sy) };
+        (!node.is_null()).then(|| SceneHit {
+            node: SceneNode::from_ptr(node),
+            sx,
+            sy,
+        })
+    }
+
+    /// Attaches a value to the node, replacing any previous one. The value is
+    /// dropped when the node is destroyed, or when it's replaced or removed.
+    /// Values are kept in a table keyed by node, so the node's raw `data`
+    /// pointer stays free for other code.
+    pub fn set_data<T: Any>(&self, value: T) {
+        let node = self.0.as_ptr();
+        let value: Rc<dyn Any> = Rc::new(value);
+
+        // Drop the previous value outside of the borrow, since its Drop may
+        // touch node data itself.
+        let previous = NODE_DATA.with(|data| {
+            data.borrow_mut()
+                .get_mut(&node)
+                .map(|data| std::mem::replace(&mut data.value, value.clone()))
+        });
+        if previous.is_some() {
+            return;
+        }
+
+        let listener = rust_listener_create(node_data_destroy);
+        unsafe {
+            rust_listener_set_user_data(listener, node as *mut c_void);
+            wl_signal_add(&mut (*node).events.destroy, listener);
+        }
+
+        let data = NodeData {
+            value,
+            destroy_listener: listener,
+        };
+        NODE_DATA.with(|node_data| node_data.borrow_mut().insert(node, data));
+    }
+
+    /// The value attached to the node, if there is one of type `T`.
+    pub fn data<T: Any>(&self) -> Option<Rc<T>> {
+        node_data(self.0.as_ptr())
+    }
+
+    /// The closest node, starting with this one and walking up its
+    /// ancestors, with a value of type `T` attached, along with the value.
+    /// This maps a hit on a surface back to the window that contains it.
+    pub fn find_data<T: Any>(&self) -> Option<(SceneNode, Rc<T>)> {
+        unsafe {
+            let mut node = self.0.as_ptr();
+            loop {
+                if let Some(value) = node_data(node) {
+                    return Some((SceneNode::from_ptr(node), value));
+                }
+
+                let parent = (*node).parent;
+                if parent.is_null() {
+                    return None;
+                }
+                node = &mut (*parent).node;
+            }
+        }
+    }
+
+    /// Detaches and drops the value attached to the node, if any.
+    pub fn remove_data(&self) {
+        let node = self.0.as_ptr();
+        let data = NODE_DATA.with(|data| data.borrow_mut().remove(&node));
+        drop(data);
+    }
+
+    /// The raw `data` pointer of the node.
+    #[deprecated(note = "use `SceneNode::data`, which is typed and owns the value")]
+    pub fn data_ptr(&self) -> *mut c_void {
+        unsafe { (*self.0.as_ptr()).data }
+    }
+
+    /// Sets the raw `data` pointer of the node. The node doesn't take
+    /// ownership of the data.
+    #[deprecated(note = "use `SceneNode::set_data`, which is typed and owns the value")]
+    pub fn set_data_ptr(&self, data: *mut c_void) {
+        unsafe { (*self.0.as_ptr()).data = data }
+    }
+
+    /// Destroys the node and all its children.
+    pub fn destroy(self) {
+        if let Ok(ptr) = self.0.try_as_ptr() {
+            unsafe { wlr_scene_node_destroy(ptr) }
+        }
+    }
+}
+
+/// Collects the boxes of the enabled rectangles in a subtree, in layout
+/// coordinates given the node's own position, along with its enabled
+/// buffers.
+fn collect_visible(
+    node: &SceneNode,
+    x: i32,
+    y: i32,
+    boxes: &mut Vec<Rect>,
+    buffers: &mut Vec<*mut wlr_scene_buffer>,
+) {
+    if !node.enabled() {
+        return;
+    }
+
+    if let Some(tree) = node.as_tree() {
+        for child in tree.children() {
+            let (cx, cy) = child.position();
+            collect_visible(&child, x + cx, y + cy, boxes, buffers);
+        }
+    } else if let Some(rect) = node.as_rect() {
+        let (width, height) = rect.size();
+        boxes.push(Rect::new(x, y, width, height));
+    } else if let Some(buffer) = node.as_buffer() {
+        buffers.push(buffer.handle().as_ptr());
+    }
+}
+
+impl Destroyable for wlr_scene_node {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
+
+/// The result of a hit test.
+pub struct SceneHit {
+    /// The node that was hit. This is always a rectangle or buffer node.
+    pub node: SceneNode,
+    /// The position of the hit, relative to the node.
+    pub sx: f64,
+    pub sy: f64,
+}
+
+impl SceneHit {
+    /// The client surface displayed by the node that was hit, if the node is
+    /// a buffer created for a surface.
+    pub fn surface(&self) -> Option<Surface> {
+        self.node.as_buffer()?.surface()
+    }
+
+    /// The value of type `T` attached to the node that was hit or its
+    /// closest ancestor with one; see [`SceneNode::find_data`].
+    pub fn data<T: Any>(&self) -> Option<Rc<T>> {
+        self.node.find_data().map(|(_, value)| value)
+    }
+
+    /// The `data` pointer of the closest node, starting with the node that
+    /// was hit and walking up its ancestors, that has one set.
+    #[deprecated(note = "use `SceneHit::data`, which is typed and owns the value")]
+    pub fn data_ptr(&self) -> *mut c_void {
+        unsafe {
+            let mut node = self.node.handle().as_ptr();
+            loop {
+                if !(*node).data.is_null() {
+                    return (*node).data;
+                }
+
+                let parent = (*node).parent;
+                if parent.is_null() {
+                    return std::ptr::null_mut();
+                }
+                node = &mut (*parent).node;
+            }
+        }
+    }
+}
+
+/// A rust value attached to a node with [`SceneNode::set_data`], owned by
+/// the node.
+struct NodeData {
+    value: Rc<dyn Any>,
+    destroy_listener: *mut wl_listener,
+}
+
+impl Drop for NodeData {
+    fn drop(&mut self) {
+        // SAFETY: the listener is attached to the node's destroy signal
+        // until the data is dropped, which the listener itself does at the
+        // latest.
+        unsafe {
+            wl_list_remove(&mut (*self.destroy_listener).link);
+            rust_listener_destroy(self.destroy_listener);
+        }
+    }
+}
+
+thread_local! {
+    // Keyed by node rather than stored in the node's `data` pointer, which
+    // other code may set to anything. The values are `Rc`s, so they can only
+    // be looked up from the thread that attached them, which is the one
+    // running the scene.
+    static NODE_DATA: RefCell<HashMap<*mut wlr_scene_node, NodeData>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Returns the value attached to a node, if it has type `T`.
+fn node_data<T: Any>(node: *mut wlr_scene_node) -> Option<Rc<T>> {
+    let value = NODE_DATA.with(|data| data.borrow().get(&node).map(|data| data.value.clone()))?;
+    value.downcast().ok()
+}
+
+unsafe extern "C" fn node_data_destroy(listener: *mut wl_listener, _data: *mut c_void) {
+    let node = rust_listener_get_user_data(listener) as *mut wlr_scene_node;
+    let data = NODE_DATA.with(|data| data.borrow_mut().remove(&node));
+    drop(data);
+}
+
+/// A node that contains other nodes, equivalent to `struct wlr_scene_tree`.
+pub struct SceneTree(Box<Handle<wlr_scene_tree>>);
+
+impl SceneTree {
+    /// Creates a new, empty subtree.
+    pub fn new(parent: &SceneTree) -> Result<Self, WlrError> {
+        let parent = parent.handle().try_as_ptr()?;
+        let ptr = unsafe { wlr_scene_tree_create(parent).as_mut() };
+
+        match ptr {
+            Some(tree) => Ok(Self(Handle::new(tree))),
+            None => Err(WlrError::CallFailed("wlr_scene_tree_create".into())),
+        }
+    }
+
+    pub fn from_ptr(ptr: *mut wlr_scene_tree) -> Self {
+        Self(Handle::new(ptr))
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_scene_tree> {
+        &self.0
+    }
+
+    /// The tree as a plain node, for positioning, stacking and so on.
+    pub fn node(&self) -> SceneNode {
+        SceneNode::from_ptr(unsafe { &mut (*self.0.as_ptr()).node })
+    }
+
+    /// The direct children of the tree, from bottom to top.
+    pub fn children(&self) -> Vec<SceneNode> {
+        let mut children = Vec::new();
+
+        unsafe {
+            let head = &mut (*self.0.as_ptr()).children as *mut wl_list;
+            list_for_each!(child, head, wlr_scene_node, link, {
+                children.push(SceneNode::from_ptr(child));
+            });
+        }
+
+        children
+    }
+
+    /// Adds a subtree displaying an xdg surface, along with its subsurfaces
+    /// and popups. The subtree is destroyed with the xdg surface. It's also
+    /// recorded on the xdg surface, so that popups can find it with
+    /// [`XdgSurface::parent_scene_tree`]. The xdg surface's `data` pointer is
+    /// left untouched.
+    pub fn add_xdg_surface(&self, xdg_surface: &XdgSurface) -> Result<SceneTree, WlrError> {
+        let parent = self.0.try_as_ptr()?;
+        let xdg_surface_ptr = xdg_surface.handle().try_as_ptr()?;
+        let tree = unsafe { wlr_scene_xdg_surface_create(parent, xdg_surface_ptr) };
+        if tree.is_null() {
+            return Err(WlrError::CallFailed("wlr_scene_xdg_surface_create".into()));
+        }
+
+        xdg_surface.set_scene_tree(tree);
+        Ok(SceneTree::from_ptr(tree))
+    }
+
+    /// Adds a buffer node displaying a single surface, without its
+    /// subsurfaces. The node is destroyed with the surface.
+    pub fn add_surface(&self, surface: &Surface) -> Result<SceneBuffer, WlrError> {
+        let parent = self.0.try_as_ptr()?;
+        let surface = surface.handle().try_as_ptr()?;
+
+        unsafe {
+            match wlr_scene_surface_create(parent, surface).as_ref() {
+                Some(scene_surface) => Ok(SceneBuffer::from_ptr(scene_surface.buffer)),
+                None => Err(WlrError::CallFailed("wlr_scene_surface_create".into())),
+            }
+        }
+    }
+
+    /// Adds a subtree displaying a surface and all its subsurfaces. The
+    /// subtree is destroyed with the surface.
+    pub fn add_subsurface_tree(&self, surface: &Surface) -> Result<SceneTree, WlrError> {
+        let parent = self.0.try_as_ptr()?;
+        let surface = surface.handle().try_as_ptr()?;
+        let tree = unsafe { wlr_scene_subsurface_tree_create(parent, surface) };
+        if tree.is_null() {
+            return Err(WlrError::CallFailed(
+                "wlr_scene_subsurface_tree_create".into(),
+            ));
+        }
+
+        Ok(SceneTree::from_ptr(tree))
+    }
+
+    /// Destroys the tree and all its children.
+    pub fn destroy(self) {
+        if let Ok(ptr) = self.0.try_as_ptr() {
+            unsafe { wlr_scene_node_destroy(&mut (*ptr).node) }
+        }
+    }
+}
+
+impl Destroyable for wlr_scene_tree {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.node.events.destroy
+    }
+}
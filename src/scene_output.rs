@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use libc::timespec;
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{
+    wlr_damage_ring_add_whole, wlr_scene_output, wlr_scene_output_build_state,
+    wlr_scene_output_commit, wlr_scene_output_destroy, wlr_scene_output_send_frame_done,
+    wlr_scene_output_set_position,
+};
+
+use crate::{monotonic_now, Destroyable, Handle, Output, OutputState, WlrError};
+
+/// The view of a scene on an output, equivalent to `struct wlr_scene_output`.
+/// Scene outputs are created with [`Scene::create_output`](crate::Scene::create_output),
+/// and are destroyed along with the scene or the output.
+pub struct SceneOutput(Box<Handle<wlr_scene_output>>);
+
+impl SceneOutput {
+    pub fn from_ptr(ptr: *mut wlr_scene_output) -> Self {
+        Self(Handle::new(ptr))
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_scene_output> {
+        &self.0
+    }
+
+    pub fn output(&self) -> Output {
+        Output::from_ptr(unsafe { (*self.0.as_ptr()).output })
+    }
+
+    /// The position of the output's top-left corner in the scene.
+    pub fn position(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).x, (*p).y) }
+    }
+
+    /// Moves the output's viewport. If the scene is attached to an output
+    /// layout, the layout sets this automatically.
+    pub fn set_position(&self, x: i32, y: i32) {
+        unsafe { wlr_scene_output_set_position(self.0.as_ptr(), x, y) }
+    }
+
+    /// Renders the scene, if anything changed, and commits the result to the
+    /// output.
+    pub fn commit(&self) -> Result<(), WlrError> {
+        unsafe {
+            if !wlr_scene_output_commit(self.0.try_as_ptr()?, std::ptr::null()) {
+                return Err(WlrError::CallFailed("wlr_scene_output_commit".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Renders the scene into a pending state, without committing it. The
+    /// state may also be left without a buffer if nothing changed.
+    pub fn build_state(&self, state: &mut OutputState) -> Result<(), WlrError> {
+        unsafe {
+            let scene_output = self.0.try_as_ptr()?;
+            if !wlr_scene_output_build_state(scene_output, state.as_mut_ptr(), std::ptr::null()) {
+                return Err(WlrError::CallFailed("wlr_scene_output_build_state".into()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Marks the whole output as damaged, so that the next render repaints
+    /// everything.
+    pub fn damage_whole(&self) {
+        unsafe { wlr_damage_ring_add_whole(&mut (*self.0.as_ptr()).damage_ring) }
+    }
+
+    /// Sends frame-done events to the surfaces visible on the output, with a
+    /// timestamp on the monotonic clock.
+    pub fn send_frame_done(&self, when: Duration) -> Result<(), WlrError> {
+        let mut when = timespec {
+            tv_sec: when.as_secs() as _,
+            tv_nsec: when.subsec_nanos() as _,
+        };
+
+        unsafe { wlr_scene_output_send_frame_done(self.0.try_as_ptr()?, &mut when) };
+        Ok(())
+    }
+
+    /// Sets up the standard render loop: on each frame of the output, the
+    /// scene is committed, and visible surfaces are sent frame-done events.
+    /// `output` must be the output that the scene output shows; the loop runs
+    /// for as long as that wrapper is alive.
+    pub fn render_on_frame(&self, output: &mut Output) -> Result<(), WlrError> {
+        let ptr = self.0.try_as_ptr()?;
+        if unsafe { (*ptr).output } != output.handle().try_as_ptr()? {
+            return Err(WlrError::SceneOutputMismatch);
+        }
+
+        let scene_output = SceneOutput::from_ptr(ptr);
+        output.on_frame(move || {
+            if scene_output.handle().try_as_ptr().is_err() {
+                return;
+            }
+
+            // A failed commit is retried on the next frame.
+            let _ = scene_output.commit();
+            let _ = scene_output.send_frame_done(monotonic_now());
+        });
+
+        Ok(())
+    }
+
+    pub fn destroy(self) {
+        if let Ok(ptr) = self.0.try_as_ptr() {
+            unsafe { wlr_scene_output_destroy(ptr) }
+        }
+    }
+}
+
+impl Destroyable for wlr_scene_output {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
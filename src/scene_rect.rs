@@ -0,0 +1,62 @@
+use wayland_sys::server::wl_signal;
+use wlroots_sys::{
+    wlr_scene_rect, wlr_scene_rect_create, wlr_scene_rect_set_color, wlr_scene_rect_set_size,
+};
+
+use crate::{Destroyable, Handle, SceneNode, SceneTree, WlrError};
+
+/// A solid-colored rectangle in the scene graph, equivalent to
+/// `struct wlr_scene_rect`. Colors are premultiplied RGBA.
+pub struct SceneRect(Box<Handle<wlr_scene_rect>>);
+
+impl SceneRect {
+    pub fn new(
+        parent: &SceneTree,
+        width: i32,
+        height: i32,
+        color: [f32; 4],
+    ) -> Result<Self, WlrError> {
+        let parent = parent.handle().try_as_ptr()?;
+        let ptr = unsafe { wlr_scene_rect_create(parent, width, height, color.as_ptr()).as_mut() };
+
+        match ptr {
+            Some(rect) => Ok(Self(Handle::new(rect))),
+            None => Err(WlrError::CallFailed("wlr_scene_rect_create".into())),
+        }
+    }
+
+    pub fn from_ptr(ptr: *mut wlr_scene_rect) -> Self {
+        Self(Handle::new(ptr))
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_scene_rect> {
+        &self.0
+    }
+
+    pub fn node(&self) -> SceneNode {
+        SceneNode::from_ptr(unsafe { &mut (*self.0.as_ptr()).node })
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).width, (*p).height) }
+    }
+
+    pub fn set_size(&self, width: i32, height: i32) {
+        unsafe { wlr_scene_rect_set_size(self.0.as_ptr(), width, height) }
+    }
+
+    pub fn color(&self) -> [f32; 4] {
+        unsafe { (*self.0.as_ptr()).color }
+    }
+
+    pub fn set_color(&self, color: [f32; 4]) {
+        unsafe { wlr_scene_rect_set_color(self.0.as_ptr(), color.as_ptr()) }
+    }
+}
+
+impl Destroyable for wlr_scene_rect {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.node.events.destroy
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use crate::{Buffer, Output, Renderer, WlrError};
+
+/// Pixels read back from a buffer into CPU memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    /// A DRM fourcc code.
+    pub format: u32,
+    /// The length of a row in bytes.
+    pub stride: usize,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Returns the bytes of a single row, or None if `y` is out of range.
+    pub fn row(&self, y: u32) -> Option<&[u8]> {
+        if y >= self.height {
+            return None;
+        }
+
+        let start = y as usize * self.stride;
+        self.data.get(start..start + self.width as usize * 4)
+    }
+
+    /// Encodes the image as a PNG. The image must be in
+    /// [`DRM_FORMAT_ABGR8888`](crate::DRM_FORMAT_ABGR8888), as returned by
+    /// [`Renderer::read_pixels`].
+    #[cfg(feature = "png")]
+    pub fn write_png(&self, w: impl std::io::Write) -> Result<(), WlrError> {
+        if self.format != crate::DRM_FORMAT_ABGR8888 {
+            return Err(WlrError::UnsupportedFormat(self.format));
+        }
+
+        let mut encoder = png::Encoder::new(w, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        let mut stream = writer.stream_writer()?;
+        for y in 0..self.height {
+            let row = self.row(y).ok_or(WlrError::InvalidBufferLayout {
+                width: self.width as i32,
+                height: self.height as i32,
+                stride: self.stride,
+                len: self.data.len(),
+            })?;
+            std::io::Write::write_all(&mut stream, row).map_err(png::EncodingError::from)?;
+        }
+        stream.finish()?;
+
+        Ok(())
+    }
+
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> Result<Vec<u8>, WlrError> {
+        let mut buf = Vec::new();
+        self.write_png(&mut buf)?;
+        Ok(buf)
+    }
+
+    /// Decodes a PNG into an image in
+    /// [`DRM_FORMAT_ABGR8888`](crate::DRM_FORMAT_ABGR8888). Images without an
+    /// alpha channel are read as opaque.
+    #[cfg(feature = "png")]
+    pub fn read_png(r: impl std::io::Read) -> Result<Self, WlrError> {
+        let mut decoder = png::Decoder::new(r);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let mut reader = decoder.read_info()?;
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf)?;
+
+        let (width, height) = (info.width, info.height);
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for row in buf.chunks(info.line_size).take(height as usize) {
+            match info.color_type {
+                png::ColorType::Rgba => data.extend_from_slice(&row[..width as usize * 4]),
+                png::ColorType::Rgb => {
+                    for p in row.chunks_exact(3).take(width as usize) {
+                        data.extend_from_slice(&[p[0], p[1], p[2], 0xff]);
+                    }
+                }
+                png::ColorType::GrayscaleAlpha => {
+                    for p in row.chunks_exact(2).take(width as usize) {
+                        data.extend_from_slice(&[p[0], p[0], p[0], p[1]]);
+                    }
+                }
+                // EXPAND turns indexed images into RGB, so this is grayscale.
+                _ => {
+                    for &v in row.iter().take(width as usize) {
+                        data.extend_from_slice(&[v, v, v, 0xff]);
+                    }
+                }
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            format: crate::DRM_FORMAT_ABGR8888,
+            stride: width as usize * 4,
+            data,
+        })
+    }
+
+    /// The RGBA value of a pixel, or None if it's out of bounds.
+    fn pixel(&self, x: u32, y: u32) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let start = y as usize * self.stride + x as usize * 4;
+        self.data.get(start..start + 4)?.try_into().ok()
+    }
+
+    /// Compares the image with an expected one, pixel by pixel. Pixels match
+    /// if no channel differs by more than `tolerance`. Both images must be in
+    /// the same format. If the sizes differ, pixels outside either image
+    /// count as different.
+    pub fn compare(&self, expected: &Image, tolerance: u8) -> Result<ImageDiff, WlrError> {
+        if self.format != expected.format {
+            return Err(WlrError::FormatMismatch {
+                expected: expected.format,
+                actual: self.format,
+            });
+        }
+
+        let width = self.width.max(expected.width);
+        let height = self.height.max(expected.height);
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        let mut differing = 0;
+        let mut max_delta = 0;
+
+        for y in 0..height {
+            for x in 0..width {
+                let delta = match (self.pixel(x, y), expected.pixel(x, y)) {
+                    (Some(a), Some(b)) => (0..4).map(|i| a[i].abs_diff(b[i])).max().unwrap_or(0),
+                    _ => u8::MAX,
+                };
+                max_delta = max_delta.max(delta);
+
+                if delta > tolerance {
+                    // Differences are bright red...
+                    differing += 1;
+                    data.extend_from_slice(&[0xff, 0, 0, 0xff]);
+                } else {
+                    // ...on top of a faded copy of the expected image.
+                    let [r, g, b, _] = expected.pixel(x, y).unwrap_or_default();
+                    let luma = (r as u32 * 3 + g as u32 * 6 + b as u32) / 10;
+                    let faded = (luma / 4 + 0xbf) as u8;
+                    data.extend_from_slice(&[faded, faded, faded, 0xff]);
+                }
+            }
+        }
+
+        Ok(ImageDiff {
+            differing,
+            max_delta,
+            image: Image {
+                width,
+                height,
+                format: self.format,
+                stride: width as usize * 4,
+                data,
+            },
+        })
+    }
+
+    /// Checks the image against a golden PNG, for rendering tests. On a
+    /// mismatch, the actual image and a diff are written next to the golden
+    /// image, as `<name>.actual.png` and `<name>.diff.png`.
+    ///
+    /// If the `WLROOTS_UPDATE_GOLDEN` environment variable is set, the golden
+    /// image is overwritten instead.
+    #[cfg(feature = "png")]
+    pub fn check_golden(
+        &self,
+        golden: impl AsRef<std::path::Path>,
+        tolerance: u8,
+    ) -> Result<(), WlrError> {
+        let golden = golden.as_ref();
+        if std::env::var_os("WLROOTS_UPDATE_GOLDEN").is_some() {
+            return self.write_png(std::fs::File::create(golden)?);
+        }
+
+        let expected = Image::read_png(std::io::BufReader::new(std::fs::File::open(golden)?))?;
+        let diff = self.compare(&expected, tolerance)?;
+        if diff.matches() {
+            return Ok(());
+        }
+
+        let actual_path = golden.with_extension("actual.png");
+        let diff_path = golden.with_extension("diff.png");
+        self.write_png(std::fs::File::create(&actual_path)?)?;
+        diff.image.write_png(std::fs::File::create(&diff_path)?)?;
+
+        Err(WlrError::ImageMismatch {
+            golden: golden.display().to_string(),
+            differing: diff.differing,
+            diff: diff_path.display().to_string(),
+        })
+    }
+}
+
+/// The result of [`Image::compare`].
+#[derive(Debug, Clone)]
+pub struct ImageDiff {
+    /// The number of pixels that differ by more than the tolerance.
+    pub differing: usize,
+    /// The largest difference in any channel of any pixel.
+    pub max_delta: u8,
+    /// A visualization of the differences: differing pixels are red, and
+    /// matching ones are a faded copy of the expected image.
+    pub image: Image,
+}
+
+impl ImageDiff {
+    pub fn matches(&self) -> bool {
+        self.differing == 0
+    }
+}
+
+/// Captures the buffer of an output's next commit, so that it can be read
+/// back. The buffer is only kept from a requested commit until it's
+/// captured, so that it isn't held back from the output's swapchain.
+///
+/// The capture stops listening to the output when it's dropped.
+pub struct OutputCapture {
+    requested: Rc<Cell<bool>>,
+    captured: Rc<RefCell<Option<Buffer>>>,
+    output: Output,
+}
+
+impl OutputCapture {
+    pub fn attach(output: &Output) -> Result<Self, WlrError> {
+        let mut output = Output::from_ptr(output.handle().try_as_ptr()?);
+        let requested = Rc::new(Cell::new(false));
+        let captured = Rc::new(RefCell::new(None));
+
+        let commit_requested = requested.clone();
+        let commit_captured = captured.clone();
+        output.on_commit(move |event| {
+            if !commit_requested.get() {
+                return;
+            }
+
+            // Only lock the buffer of a commit that was asked for.
+            if let Some(buffer) = event.buffer() {
+                commit_requested.set(false);
+                commit_captured.replace(Some(buffer));
+            }
+        });
+
+        Ok(Self {
+            requested,
+            captured,
+            output,
+        })
+    }
+
+    /// Asks for the buffer of the next commit with one, and schedules a
+    /// frame so that there is one. Any previously captured buffer is
+    /// released.
+    pub fn request(&self) {
+        self.captured.replace(None);
+        self.requested.set(true);
+        if self.output.handle().try_as_ptr().is_ok() {
+            self.output.schedule_frame();
+        }
+    }
+
+    /// Returns true if a requested buffer has been captured and not read
+    /// back yet.
+    pub fn is_ready(&self) -> bool {
+        self.captured.borrow().is_some()
+    }
+
+    /// Takes the captured buffer, if any. The caller's lock is the only
+    /// thing keeping it from the swapchain.
+    pub fn take_buffer(&self) -> Option<Buffer> {
+        self.captured.take()
+    }
+
+    /// Reads back the captured buffer, and releases it.
+    pub fn capture(&self, renderer: &Renderer) -> Result<Image, WlrError> {
+        match self.take_buffer() {
+            Some(buffer) => renderer.read_pixels(&buffer),
+            None => Err(WlrError::NothingCaptured),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DRM_FORMAT_ABGR8888, DRM_FORMAT_ARGB8888};
+
+    fn solid(width: u32, height: u32, rgba: [u8; 4]) -> Image {
+        Image {
+            width,
+            height,
+            format: DRM_FORMAT_ABGR8888,
+            stride: width as usize * 4,
+            data: rgba.repeat(width as usize * height as usize),
+        }
+    }
+
+    #[test]
+    fn rows_respect_stride() {
+        let image = Image {
+            width: 1,
+            height: 2,
+            format: DRM_FORMAT_ABGR8888,
+            stride: 8,
+            data: vec![1, 2, 3, 4, 0, 0, 0, 0, 5, 6, 7, 8],
+        };
+
+        assert_eq!(image.row(0), Some(&[1, 2, 3, 4][..]));
+        assert_eq!(image.row(1), Some(&[5, 6, 7, 8][..]));
+        assert_eq!(image.row(2), None);
+        assert_eq!(image.pixel(0, 1), Some([5, 6, 7, 8]));
+        assert_eq!(image.pixel(1, 0), None);
+    }
+
+    #[test]
+    fn truncated_data_has_no_pixels() {
+        let image = Image {
+            data: vec![0; 6],
+            ..solid(1, 2, [0; 4])
+        };
+
+        assert_eq!(image.row(1), None);
+        assert_eq!(image.pixel(0, 1), None);
+    }
+
+    #[test]
+    fn compare_within_tolerance() {
+        let expected = solid(2, 2, [100, 100, 100, 255]);
+        let mut actual = expected.clone();
+        actual.data[0] = 103;
+
+        let diff = actual.compare(&expected, 3).unwrap();
+        assert!(diff.matches());
+        assert_eq!(diff.max_delta, 3);
+
+        let diff = actual.compare(&expected, 2).unwrap();
+        assert_eq!(diff.differing, 1);
+        assert_eq!(diff.image.pixel(0, 0), Some([0xff, 0, 0, 0xff]));
+        assert_ne!(diff.image.pixel(1, 0), Some([0xff, 0, 0, 0xff]));
+    }
+
+    #[test]
+    fn compare_counts_pixels_outside_either_image() {
+        let diff = solid(2, 1, [0; 4])
+            .compare(&solid(1, 2, [0; 4]), 0)
+            .unwrap();
+
+        assert_eq!((diff.image.width, diff.image.height), (2, 2));
+        assert_eq!(diff.differing, 3);
+        assert_eq!(diff.max_delta, u8::MAX);
+    }
+
+    #[test]
+    fn compare_rejects_mismatched_formats() {
+        let expected = solid(1, 1, [0; 4]);
+        let actual = Image {
+            format: DRM_FORMAT_ARGB8888,
+            ..expected.clone()
+        };
+
+        assert!(matches!(
+            actual.compare(&expected, 0),
+            Err(WlrError::FormatMismatch {
+                expected: DRM_FORMAT_ABGR8888,
+                actual: DRM_FORMAT_ARGB8888,
+            })
+        ));
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn png_round_trip() {
+        let mut image = solid(3, 2, [10, 20, 30, 255]);
+        image.data[4..8].copy_from_slice(&[200, 0, 0, 128]);
+
+        let png = image.to_png().unwrap();
+        assert_eq!(Image::read_png(&png[..]).unwrap(), image);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn read_png_makes_rgb_opaque() {
+        let mut png = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png, 2, 1);
+            encoder.set_color(png::ColorType::Rgb);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[1, 2, 3, 4, 5, 6]).unwrap();
+        }
+
+        let image = Image::read_png(&png[..]).unwrap();
+        assert_eq!(image.format, DRM_FORMAT_ABGR8888);
+        assert_eq!(image.data, [1, 2, 3, 255, 4, 5, 6, 255]);
+    }
+}
@@ -0,0 +1,45 @@
+use wayland_sys::server::{wl_resource_get_id, wl_signal};
+use wlroots_sys::wlr_surface;
+
+use crate::{Destroyable, Handle};
+
+/// A client surface, equivalent to `struct wlr_surface`. Two wrappers are
+/// equal if they were created for the same surface, even after it's
+/// destroyed.
+pub struct Surface(Box<Handle<wlr_surface>>, *mut wlr_surface);
+
+impl Surface {
+    pub fn from_ptr(ptr: *mut wlr_surface) -> Self {
+        Self(Handle::new(ptr), ptr)
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_surface> {
+        &self.0
+    }
+
+    /// The protocol object id of the surface, unique within its client.
+    pub fn id(&self) -> u32 {
+        unsafe { wl_resource_get_id((*self.0.as_ptr()).resource) }
+    }
+
+    /// The size of the surface's current state, in surface-local
+    /// coordinates.
+    pub fn size(&self) -> (i32, i32) {
+        let p = self.0.as_ptr();
+        unsafe { ((*p).current.width, (*p).current.height) }
+    }
+}
+
+impl PartialEq for Surface {
+    fn eq(&self, other: &Self) -> bool {
+        // The handle forgets its pointer when the surface is destroyed, which
+        // would make every destroyed surface equal.
+        self.1 == other.1
+    }
+}
+
+impl Destroyable for wlr_surface {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
@@ -0,0 +1,64 @@
+use wayland_server::DisplayHandle;
+use wayland_sys::{common::wl_list, server::wl_signal};
+use wlroots_sys::{wlr_xdg_output_manager_v1, wlr_xdg_output_manager_v1_create, wlr_xdg_output_v1};
+
+use crate::{macros::*, Destroyable, Handle, Output, OutputLayout, Rect, WlrError};
+
+/// Implements the xdg-output-unstable-v1 protocol, which tells clients like
+/// status bars and screenshot tools the logical position and size of each
+/// output in the layout.
+///
+/// The manager is destroyed along with the layout it was created for; after
+/// that, the handle reports it as destroyed.
+pub struct XdgOutputManager(Box<Handle<wlr_xdg_output_manager_v1>>);
+
+impl XdgOutputManager {
+    pub fn new(display: &DisplayHandle, layout: &OutputLayout) -> Result<Self, WlrError> {
+        let display = display.backend_handle().display_ptr();
+        let layout = layout.handle().try_as_ptr()?;
+        let ptr = unsafe { wlr_xdg_output_manager_v1_create(display, layout).as_mut() };
+
+        match ptr {
+            Some(v) => Ok(Self(Handle::new(v))),
+            None => Err(WlrError::CallFailed(
+                "wlr_xdg_output_manager_v1_create".into(),
+            )),
+        }
+    }
+
+    pub fn handle(&self) -> &Handle<wlr_xdg_output_manager_v1> {
+        &self.0
+    }
+
+    /// Returns true if the manager is still alive, which is the case as long
+    /// as its layout is.
+    pub fn is_alive(&self) -> bool {
+        self.0.try_as_ptr().is_ok()
+    }
+
+    /// The logical position and size that the manager advertises for an
+    /// output, or None if the output isn't in the layout. The size takes the
+    /// output's scale and transform into account.
+    pub fn logical_geometry(&self, output: &Output) -> Result<Option<Rect>, WlrError> {
+        let manager = self.0.try_as_ptr()?;
+        let output = output.handle().try_as_ptr()?;
+
+        unsafe {
+            let head = &mut (*manager).outputs as *mut wl_list;
+            list_for_each!(xdg_output, head, wlr_xdg_output_v1, link, {
+                if (*(*xdg_output).layout_output).output == output {
+                    let x = &*xdg_output;
+                    return Ok(Some(Rect::new(x.x, x.y, x.width, x.height)));
+                }
+            });
+        }
+
+        Ok(None)
+    }
+}
+
+impl Destroyable for wlr_xdg_output_manager_v1 {
+    fn destroy_signal(&mut self) -> *mut wl_signal {
+        &mut self.events.destroy
+    }
+}
@@ -1,8 +1,20 @@
+use std::{cell::RefCell, collections::HashMap, os::raw::c_void};
+
 use wayland_server::DisplayHandle;
-use wayland_sys::server::wl_signal;
-use wlroots_sys::{wlr_xdg_shell, wlr_xdg_shell_create, wlr_xdg_surface};
+use wayland_sys::server::{
+    signal::{
+        rust_listener_create, rust_listener_destroy, rust_listener_get_user_data,
+        rust_listener_set_user_data, wl_signal_add,
+    },
+    wl_list_remove, wl_listener, wl_signal,
+};
+use wlroots_sys::{
+    wlr_scene_tree, wlr_xdg_popup, wlr_xdg_popup_try_from_wlr_surface, wlr_xdg_shell,
+    wlr_xdg_shell_create, wlr_xdg_surface, wlr_xdg_surface_role_WLR_XDG_SURFACE_ROLE_POPUP,
+    wlr_xdg_surface_role_WLR_XDG_SURFACE_ROLE_TOPLEVEL, wlr_xdg_surface_try_from_wlr_surface,
+};
 
-use crate::{Destroyable, Handle, WlrError};
+use crate::{Destroyable, Handle, SceneTree, Surface, WlrError};
 
 pub struct XdgShell(Box<Handle<wlr_xdg_shell>>);
 
@@ -35,29 +47,85 @@ impl Destroyable for wlr_xdg_shell {
     }
 }
 
-pub enum XdgSurface {
-    XdgSurfaceToplevel {
-        surf: Box<Handle<wlr_xdg_surface>>,
-        toplevel: Box<Handle<wlr_xdg_toplevel>>,
-    },
-    XdgSurfacePopup(Box<Handle<wlr_xdg_surface>>),
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum XdgSurfaceRole {
+    /// The client hasn't assigned a role yet.
+    None,
+    Toplevel,
+    Popup,
 }
 
-pub struct XdgSurfaceToplevel(Box<Handle<wlr_xdg_surface>>);
+pub struct XdgSurface(Box<Handle<wlr_xdg_surface>>);
 
 impl XdgSurface {
     pub fn from_ptr(ptr: *mut wlr_xdg_surface) -> Self {
-        unsafe {
-            if (*ptr).toplevel.is_some() {
-                XdgSurfaceToplevel(Box::new(Handle::new(ptr)))
-            }
-        }
+        Self(Handle::new(ptr))
+    }
+
+    /// Returns the xdg surface for a surface, if the surface has the xdg
+    /// surface role.
+    pub fn from_surface(surface: &Surface) -> Option<Self> {
+        let ptr = unsafe { wlr_xdg_surface_try_from_wlr_surface(surface.handle().as_ptr()) };
+        (!ptr.is_null()).then(|| Self::from_ptr(ptr))
     }
 
     pub fn handle(&self) -> &Handle<wlr_xdg_surface> {
         &self.0
     }
 
+    pub fn role(&self) -> XdgSurfaceRole {
+        match unsafe { (*self.0.as_ptr()).role } {
+            wlr_xdg_surface_role_WLR_XDG_SURFACE_ROLE_TOPLEVEL => XdgSurfaceRole::Toplevel,
+            wlr_xdg_surface_role_WLR_XDG_SURFACE_ROLE_POPUP => XdgSurfaceRole::Popup,
+            _ => XdgSurfaceRole::None,
+        }
+    }
+
+    /// The underlying client surface.
+    pub fn surface(&self) -> Surface {
+        Surface::from_ptr(unsafe { (*self.0.as_ptr()).surface })
+    }
+
+    /// For popups, the surface the popup is attached to.
+    pub fn popup_parent(&self) -> Option<Surface> {
+        // The popup pointer shares a union with the toplevel one, so only
+        // trust it through wlroots' own role check.
+        let parent = unsafe {
+            let popup = wlr_xdg_popup_try_from_wlr_surface((*self.0.as_ptr()).surface);
+            popup.as_ref()?.parent
+        };
+        (!parent.is_null()).then(|| Surface::from_ptr(parent))
+    }
+
+    /// The scene tree created for this surface with
+    /// [`SceneTree::add_xdg_surface`], if any.
+    pub fn scene_tree(&self) -> Option<SceneTree> {
+        let xdg_surface = self.0.as_ptr();
+        let tree = SCENE_TREES.with(|trees| trees.borrow().get(&xdg_surface).copied())?;
+        Some(SceneTree::from_ptr(tree))
+    }
+
+    /// For popups, the scene tree of the parent xdg surface. Adding the popup
+    /// to this tree keeps it positioned relative to its parent.
+    pub fn parent_scene_tree(&self) -> Option<SceneTree> {
+        let parent = self.popup_parent()?;
+        XdgSurface::from_surface(&parent)?.scene_tree()
+    }
+
+    /// Records the scene tree of the surface. The entry is removed when the
+    /// tree is destroyed, so that it never dangles.
+    pub(crate) fn set_scene_tree(&self, tree: *mut wlr_scene_tree) {
+        let xdg_surface = self.0.as_ptr();
+        let listener = rust_listener_create(scene_tree_destroy);
+        let link = Box::into_raw(Box::new(SceneTreeLink { xdg_surface, tree }));
+
+        SCENE_TREES.with(|trees| trees.borrow_mut().insert(xdg_surface, tree));
+        unsafe {
+            rust_listener_set_user_data(listener, link as *mut c_void);
+            wl_signal_add(&mut (*tree).node.events.destroy, listener);
+        }
+    }
+
     pub fn on_ping_timeout(&mut self, cb: impl Fn() + 'static) {
         let signal = unsafe { &mut (*self.handle().as_ptr()).events.ping_timeout };
         self.0.add_listener(signal, move |_| {
@@ -68,9 +136,42 @@ impl XdgSurface {
     pub fn on_new_popup(&mut self, cb: impl Fn(XdgSurface) + 'static) {
         let signal = unsafe { &mut (*self.handle().as_ptr()).events.new_popup };
         self.0.add_listener(signal, move |data| {
-            (cb)(XdgSurface::from_ptr(data as *mut _));
+            let popup = data as *mut wlr_xdg_popup;
+            (cb)(XdgSurface::from_ptr(unsafe { (*popup).base }));
         });
     }
+
+    /// Called just before the xdg surface is destroyed.
+    pub fn on_destroy(&mut self, cb: impl Fn() + 'static) {
+        self.0.add_destroy_callback(cb);
+    }
+}
+
+thread_local! {
+    // The xdg surface's own `data` pointer is left to the compositor, which
+    // commonly uses it for the same purpose; the crate keeps its trees here.
+    static SCENE_TREES: RefCell<HashMap<*mut wlr_xdg_surface, *mut wlr_scene_tree>> =
+        RefCell::new(HashMap::new());
+}
+
+struct SceneTreeLink {
+    xdg_surface: *mut wlr_xdg_surface,
+    tree: *mut wlr_scene_tree,
+}
+
+unsafe extern "C" fn scene_tree_destroy(listener: *mut wl_listener, _data: *mut c_void) {
+    let link = Box::from_raw(rust_listener_get_user_data(listener) as *mut SceneTreeLink);
+
+    // The surface may have been given a newer tree since, which stays.
+    SCENE_TREES.with(|trees| {
+        let mut trees = trees.borrow_mut();
+        if trees.get(&link.xdg_surface) == Some(&link.tree) {
+            trees.remove(&link.xdg_surface);
+        }
+    });
+
+    wl_list_remove(&mut (*listener).link);
+    rust_listener_destroy(listener);
 }
 
 impl Destroyable for wlr_xdg_surface {
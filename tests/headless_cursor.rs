@@ -0,0 +1,37 @@
+use wlroots::{HeadlessHarness, OutputCursor, Scene};
+
+const RED: [u8; 4] = [0xff, 0, 0, 0xff];
+const BLACK: [u8; 4] = [0, 0, 0, 0xff];
+
+#[test]
+fn software_cursor_is_drawn_at_its_position() {
+    let harness = HeadlessHarness::new(64, 64).unwrap();
+    let cursor = OutputCursor::new(harness.output()).unwrap();
+    assert!(!cursor.visible());
+
+    // An opaque red 8x8 image; ARGB8888 is BGRA in memory.
+    let pixels = [0, 0, 0xff, 0xff].repeat(8 * 8);
+    cursor.set_image(8, 8, pixels, 2, 2).unwrap();
+    cursor.move_to(22.0, 12.0).unwrap();
+    assert_eq!(cursor.position().unwrap(), (22.0, 12.0));
+    assert!(cursor.visible());
+
+    // Headless outputs have no cursor plane.
+    assert!(!cursor.is_hardware());
+
+    // The image's top-left corner is at the position minus the hotspot.
+    let image = harness.render(&Scene::new().unwrap()).unwrap();
+    let pixel = |x: usize, y: u32| &image.row(y).unwrap()[x * 4..x * 4 + 4];
+    assert_eq!(pixel(20, 10), RED);
+    assert_eq!(pixel(27, 17), RED);
+    assert_eq!(pixel(19, 10), BLACK);
+    assert_eq!(pixel(28, 10), BLACK);
+    assert_eq!(pixel(20, 18), BLACK);
+
+    cursor.move_to(100.0, 100.0).unwrap();
+    assert!(!cursor.visible());
+
+    cursor.move_to(0.0, 0.0).unwrap();
+    cursor.hide().unwrap();
+    assert!(!cursor.visible());
+}
@@ -0,0 +1,21 @@
+use wlroots::{HeadlessHarness, OutputState};
+
+#[test]
+fn headless_output_rejects_gamma_lut_it_cannot_apply() {
+    let harness = HeadlessHarness::new(64, 64).unwrap();
+    let output = harness.output();
+
+    // Headless outputs have no gamma ramps, so a LUT can't be applied, but a
+    // state without one is fine.
+    assert_eq!(output.gamma_size(), 0);
+
+    let mut state = OutputState::new();
+    let ramp: Vec<u16> = (0..256).map(|i| i * 257).collect();
+    state.set_gamma_lut(&ramp, &ramp, &ramp).unwrap();
+    assert_eq!(state.gamma_lut().map(|lut| lut.len()), Some(256 * 3));
+    assert!(!output.test_state(&state));
+
+    let mut state = OutputState::new();
+    state.set_enabled(true);
+    assert!(output.test_state(&state));
+}
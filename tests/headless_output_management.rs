@@ -0,0 +1,91 @@
+use std::{cell::RefCell, rc::Rc};
+
+use wayland_server::protocol::wl_output::Transform;
+use wayland_sys::server::signal::wl_signal_emit;
+use wlroots::{ConfigurationResult, HeadlessHarness, OutputManager};
+use wlroots_sys::{
+    wl_output_transform, wlr_output_configuration_head_v1_create, wlr_output_configuration_v1,
+    wlr_output_configuration_v1_create,
+};
+
+/// Builds the configuration a client would send to rotate the harness
+/// output and double its scale.
+fn rotated_config(harness: &HeadlessHarness) -> *mut wlr_output_configuration_v1 {
+    unsafe {
+        let config = wlr_output_configuration_v1_create();
+        let head =
+            wlr_output_configuration_head_v1_create(config, harness.output().handle().as_ptr());
+        (*head).state.transform = Transform::_90 as wl_output_transform;
+        (*head).state.scale = 2.0;
+        config
+    }
+}
+
+#[test]
+fn apply_and_test_configurations() {
+    let harness = HeadlessHarness::new(64, 64).unwrap();
+    let mut manager = OutputManager::new(&harness.display_handle()).unwrap();
+
+    // The configurations the compositor saw, as the mode size, transform and
+    // scale of their single head.
+    let applied = Rc::new(RefCell::new(Vec::new()));
+    let tested = Rc::new(RefCell::new(Vec::new()));
+
+    let applied_clone = applied.clone();
+    manager.on_apply(move |config| {
+        let head = &config.heads()[0];
+        applied_clone.borrow_mut().push((
+            head.mode.map(|m| m.dimensions()),
+            head.transform,
+            head.scale,
+        ));
+
+        let result = match head.output.commit_state(&head.to_state()) {
+            Ok(()) => ConfigurationResult::Succeeded,
+            Err(_) => ConfigurationResult::Failed,
+        };
+        config.reply(result);
+    });
+
+    let tested_clone = tested.clone();
+    manager.on_test(move |config| {
+        let head = &config.heads()[0];
+        tested_clone.borrow_mut().push((
+            head.mode.map(|m| m.dimensions()),
+            head.transform,
+            head.scale,
+        ));
+
+        let result = if head.output.test_state(&head.to_state()) {
+            ConfigurationResult::Succeeded
+        } else {
+            ConfigurationResult::Failed
+        };
+        config.reply(result);
+    });
+
+    let manager_ptr = manager.handle().as_ptr();
+    let output = harness.output();
+    // Headless outputs use a custom mode, whose refresh rate the backend
+    // picks, so only the size is checked.
+    let mode = Some((64, 64));
+
+    // Testing a configuration leaves the output alone.
+    unsafe {
+        let config = rotated_config(&harness);
+        wl_signal_emit(&mut (*manager_ptr).events.test, config as *mut _);
+    }
+    assert_eq!(*tested.borrow(), [(mode, Transform::_90, 2.0)]);
+    assert!(applied.borrow().is_empty());
+    assert_eq!(output.transform(), Transform::Normal);
+    assert_eq!(output.scale(), 1.0);
+
+    unsafe {
+        let config = rotated_config(&harness);
+        wl_signal_emit(&mut (*manager_ptr).events.apply, config as *mut _);
+    }
+    assert_eq!(*applied.borrow(), [(mode, Transform::_90, 2.0)]);
+    assert_eq!(output.transform(), Transform::_90);
+    assert_eq!(output.scale(), 2.0);
+    assert!(output.enabled());
+}
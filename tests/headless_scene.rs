@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use wlroots::{
+    Buffer, HeadlessHarness, Image, Scene, SceneBuffer, SceneDump, SceneRect, SceneTree, WlrError,
+    DRM_FORMAT_ABGR8888,
+};
+
+const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+
+/// A 32x32 scene with a 16x8 red rectangle at (8, 8), on the black
+/// background that the scene clears to.
+fn render_rect() -> Image {
+    let harness = HeadlessHarness::new(32, 32).unwrap();
+    let scene = Scene::new().unwrap();
+    let rect = SceneRect::new(&scene.tree(), 16, 8, RED).unwrap();
+    rect.node().set_position(8, 8);
+
+    harness.render(&scene).unwrap()
+}
+
+#[test]
+fn scene_rect_renders_at_its_position() {
+    let image = render_rect();
+
+    let mut data = Vec::new();
+    for y in 0..32 {
+        for x in 0..32 {
+            let inside = (8..24).contains(&x) && (8..16).contains(&y);
+            data.extend_from_slice(if inside {
+                &[0xff, 0, 0, 0xff]
+            } else {
+                &[0, 0, 0, 0xff]
+            });
+        }
+    }
+    let expected = Image {
+        width: 32,
+        height: 32,
+        format: DRM_FORMAT_ABGR8888,
+        stride: 32 * 4,
+        data,
+    };
+
+    let diff = image.compare(&expected, 0).unwrap();
+    assert!(diff.matches(), "{} pixels differ", diff.differing);
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn scene_rect_matches_golden() {
+    render_rect()
+        .check_golden(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/scene_rect.png"),
+            0,
+        )
+        .unwrap();
+}
+
+#[test]
+fn dropping_the_scene_invalidates_its_wrappers() {
+    let harness = HeadlessHarness::new(32, 32).unwrap();
+    let scene = Scene::new().unwrap();
+    let root = scene.tree();
+    let tree = SceneTree::new(&root).unwrap();
+    let rect = SceneRect::new(&tree, 16, 8, RED).unwrap();
+    let node = rect.node();
+    let sibling = SceneRect::new(&tree, 4, 4, RED).unwrap().node();
+    let scene_output = scene.create_output(harness.output()).unwrap();
+
+    drop(scene);
+
+    assert!(matches!(
+        root.handle().try_as_ptr(),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+    assert!(matches!(
+        SceneTree::new(&tree),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+    assert!(matches!(
+        SceneRect::new(&tree, 1, 1, RED),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+    assert!(matches!(
+        node.place_above(&sibling),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+    assert!(matches!(
+        node.reparent(&root),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+    assert!(matches!(
+        scene_output.commit(),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+    assert!(matches!(
+        scene_output.send_frame_done(Duration::ZERO),
+        Err(WlrError::ObjectDestroyed(_))
+    ));
+
+    // The output itself outlives the scene.
+    assert!(harness.output().handle().try_as_ptr().is_ok());
+}
+
+#[test]
+fn dump_of_a_rendered_scene() {
+    let harness = HeadlessHarness::new(32, 32).unwrap();
+    let scene = Scene::new().unwrap();
+    let window = SceneTree::new(&scene.tree()).unwrap();
+    window.node().set_position(4, 2);
+    window.node().set_data(String::from("window"));
+    SceneRect::new(&window, 16, 8, RED).unwrap();
+    let pixels = Buffer::from_pixels(2, 3, DRM_FORMAT_ABGR8888, 8, vec![0xff; 24]).unwrap();
+    let buffer = SceneBuffer::new(&window, Some(&pixels)).unwrap();
+    buffer.node().set_position(1, 1);
+    buffer.node().set_enabled(false);
+
+    harness.render(&scene).unwrap();
+
+    let dump = SceneDump::with_labels(&scene.tree().node(), &|node| {
+        node.data::<String>().map(|label| (*label).clone())
+    });
+    assert_eq!(
+        dump.to_text(),
+        r#"tree (0, 0)
+  tree (4, 2) "window"
+    rect (0, 0) 16x8 color=[1.0, 0.0, 0.0, 1.0]
+    buffer (1, 1) 2x3 disabled
+"#
+    );
+    assert_eq!(
+        dump.to_json(),
+        concat!(
+            r#"{"type":"tree","x":0,"y":0,"enabled":true,"children":["#,
+            r#"{"type":"tree","x":4,"y":2,"enabled":true,"label":"window","children":["#,
+            r#"{"type":"rect","x":0,"y":0,"enabled":true,"width":16,"height":8,"#,
+            r#""color":[1,0,0,1],"children":[]},"#,
+            r#"{"type":"buffer","x":1,"y":1,"enabled":false,"width":2,"height":3,"#,
+            r#""children":[]}]}]}"#,
+        )
+    );
+}
@@ -0,0 +1,40 @@
+use wlroots::{HeadlessHarness, OutputLayout, OutputState, Rect, XdgOutputManager};
+
+#[test]
+fn xdg_output_follows_the_layout() {
+    let harness = HeadlessHarness::new(64, 48).unwrap();
+    let output = harness.output();
+    let layout = OutputLayout::new().unwrap();
+    let manager = XdgOutputManager::new(&harness.display_handle(), &layout).unwrap();
+
+    assert_eq!(manager.logical_geometry(output).unwrap(), None);
+
+    layout.add(output, 100, 50).unwrap();
+    assert_eq!(
+        manager.logical_geometry(output).unwrap(),
+        Some(Rect::new(100, 50, 64, 48))
+    );
+
+    // Scaling shrinks the logical size.
+    let mut state = OutputState::new();
+    state.set_scale(2.0);
+    output.commit_state(&state).unwrap();
+    assert_eq!(
+        manager.logical_geometry(output).unwrap(),
+        Some(Rect::new(100, 50, 32, 24))
+    );
+
+    layout.add(output, -32, 0).unwrap();
+    assert_eq!(
+        manager.logical_geometry(output).unwrap(),
+        Some(Rect::new(-32, 0, 32, 24))
+    );
+
+    layout.remove(output);
+    assert_eq!(manager.logical_geometry(output).unwrap(), None);
+
+    // The manager goes away with its layout.
+    drop(layout);
+    assert!(!manager.is_alive());
+    assert!(manager.logical_geometry(output).is_err());
+}
@@ -0,0 +1,71 @@
+use std::{cell::Cell, rc::Rc};
+
+use wlroots::{Scene, SceneRect, SceneTree};
+
+#[test]
+fn node_data_is_typed_and_inherited() {
+    let scene = Scene::new().unwrap();
+    let window = SceneTree::new(&scene.tree()).unwrap();
+    let rect = SceneRect::new(&window, 10, 10, [1.0; 4]).unwrap();
+
+    window.node().set_data(String::from("window"));
+    assert_eq!(
+        window
+            .node()
+            .data::<String>()
+            .as_deref()
+            .map(String::as_str),
+        Some("window")
+    );
+    assert!(window.node().data::<u32>().is_none());
+    assert!(rect.node().data::<String>().is_none());
+
+    let (node, title) = rect.node().find_data::<String>().unwrap();
+    assert_eq!(node.handle().as_ptr(), window.node().handle().as_ptr());
+    assert_eq!(*title, "window");
+
+    let hit = scene.node_at(5.0, 5.0).unwrap();
+    assert_eq!(
+        hit.data::<String>().as_deref().map(String::as_str),
+        Some("window")
+    );
+}
+
+#[test]
+#[allow(deprecated)]
+fn raw_data_pointer_does_not_confuse_node_data() {
+    let scene = Scene::new().unwrap();
+    let node = SceneTree::new(&scene.tree()).unwrap().node();
+
+    let mut raw = 42u32;
+    node.set_data_ptr(&mut raw as *mut u32 as *mut _);
+    assert!(node.data::<u32>().is_none());
+
+    node.set_data(7u32);
+    assert_eq!(node.data::<u32>().as_deref(), Some(&7));
+    assert_eq!(node.data_ptr(), &mut raw as *mut u32 as *mut _);
+}
+
+struct DropFlag(Rc<Cell<bool>>);
+
+impl Drop for DropFlag {
+    fn drop(&mut self) {
+        self.0.set(true);
+    }
+}
+
+#[test]
+fn node_data_is_dropped_with_the_node() {
+    let scene = Scene::new().unwrap();
+    let tree = SceneTree::new(&scene.tree()).unwrap();
+
+    let replaced = Rc::new(Cell::new(false));
+    tree.node().set_data(DropFlag(replaced.clone()));
+    let destroyed = Rc::new(Cell::new(false));
+    tree.node().set_data(DropFlag(destroyed.clone()));
+    assert!(replaced.get());
+    assert!(!destroyed.get());
+
+    tree.node().destroy();
+    assert!(destroyed.get());
+}
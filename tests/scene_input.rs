@@ -0,0 +1,38 @@
+use std::rc::Rc;
+
+use wlroots::{Buffer, Scene, SceneBuffer, DRM_FORMAT_ABGR8888};
+
+fn buffer_node(scene: &Scene) -> SceneBuffer {
+    let pixels = Buffer::from_pixels(4, 4, DRM_FORMAT_ABGR8888, 16, vec![0xff; 64]).unwrap();
+    SceneBuffer::new(&scene.tree(), Some(&pixels)).unwrap()
+}
+
+#[test]
+fn point_accepts_input_can_be_set_and_cleared() {
+    let scene = Scene::new().unwrap();
+    let buffer = buffer_node(&scene);
+
+    // Only the left half of the node accepts input.
+    buffer.set_point_accepts_input(|x, _| x < 2).unwrap();
+    assert!(scene.node_at(1.0, 1.0).is_some());
+    assert!(scene.node_at(3.0, 1.0).is_none());
+
+    buffer.clear_point_accepts_input().unwrap();
+    assert!(scene.node_at(3.0, 1.0).is_some());
+}
+
+#[test]
+fn point_accepts_input_is_dropped_with_the_node() {
+    let scene = Scene::new().unwrap();
+    let buffer = buffer_node(&scene);
+    let token = Rc::new(());
+
+    let held = token.clone();
+    buffer
+        .set_point_accepts_input(move |_, _| Rc::strong_count(&held) > 0)
+        .unwrap();
+    assert_eq!(Rc::strong_count(&token), 2);
+
+    buffer.node().destroy();
+    assert_eq!(Rc::strong_count(&token), 1);
+}
@@ -0,0 +1,93 @@
+use wlroots::{Scene, SceneNode, SceneRect, SceneTree, WlrError};
+use wlroots_sys::wlr_scene_node;
+
+fn rect(parent: &SceneTree) -> SceneNode {
+    SceneRect::new(parent, 1, 1, [1.0; 4]).unwrap().node()
+}
+
+fn ptr(node: &SceneNode) -> *mut wlr_scene_node {
+    node.handle().as_ptr()
+}
+
+/// The children of a tree, from bottom to top.
+fn stack(tree: &SceneTree) -> Vec<*mut wlr_scene_node> {
+    tree.children().iter().map(ptr).collect()
+}
+
+#[test]
+fn restacking_siblings() {
+    let scene = Scene::new().unwrap();
+    let root = scene.tree();
+    let (a, b, c) = (rect(&root), rect(&root), rect(&root));
+    assert_eq!(stack(&root), [ptr(&a), ptr(&b), ptr(&c)]);
+
+    a.raise_to_top();
+    assert_eq!(stack(&root), [ptr(&b), ptr(&c), ptr(&a)]);
+
+    a.lower_to_bottom();
+    assert_eq!(stack(&root), [ptr(&a), ptr(&b), ptr(&c)]);
+
+    a.place_above(&b).unwrap();
+    assert_eq!(stack(&root), [ptr(&b), ptr(&a), ptr(&c)]);
+
+    c.place_below(&b).unwrap();
+    assert_eq!(stack(&root), [ptr(&c), ptr(&b), ptr(&a)]);
+}
+
+#[test]
+fn restacking_rejects_non_siblings() {
+    let scene = Scene::new().unwrap();
+    let root = scene.tree();
+    let a = rect(&root);
+    let subtree = SceneTree::new(&root).unwrap();
+    let nested = rect(&subtree);
+
+    assert!(matches!(a.place_above(&a), Err(WlrError::NotSiblings)));
+    assert!(matches!(a.place_below(&a), Err(WlrError::NotSiblings)));
+    assert!(matches!(a.place_above(&nested), Err(WlrError::NotSiblings)));
+    assert!(matches!(nested.place_below(&a), Err(WlrError::NotSiblings)));
+
+    // Nothing moved.
+    assert_eq!(stack(&root), [ptr(&a), ptr(&subtree.node())]);
+    assert_eq!(stack(&subtree), [ptr(&nested)]);
+}
+
+#[test]
+fn reparenting() {
+    let scene = Scene::new().unwrap();
+    let root = scene.tree();
+    let window = SceneTree::new(&root).unwrap();
+    let content = SceneTree::new(&window).unwrap();
+    let a = rect(&root);
+    a.set_position(3, 4);
+
+    a.reparent(&content).unwrap();
+    assert_eq!(stack(&content), [ptr(&a)]);
+    assert_eq!(stack(&root), [ptr(&window.node())]);
+    assert_eq!(a.position(), (3, 4));
+    assert_eq!(
+        a.parent().map(|parent| parent.handle().as_ptr()),
+        Some(content.handle().as_ptr())
+    );
+}
+
+#[test]
+fn reparenting_rejects_cycles() {
+    let scene = Scene::new().unwrap();
+    let root = scene.tree();
+    let window = SceneTree::new(&root).unwrap();
+    let content = SceneTree::new(&window).unwrap();
+
+    assert!(matches!(
+        window.node().reparent(&window),
+        Err(WlrError::ReparentCycle)
+    ));
+    assert!(matches!(
+        window.node().reparent(&content),
+        Err(WlrError::ReparentCycle)
+    ));
+
+    // Nothing moved.
+    assert_eq!(stack(&root), [ptr(&window.node())]);
+    assert_eq!(stack(&window), [ptr(&content.node())]);
+}
@@ -39,6 +39,7 @@ fn main() {
         .allowlist_function("_?wlr_.*")
         .allowlist_var("WLR_.*")
         .allowlist_type("pixman_.*")
+        .allowlist_function("pixman_region32_.*")
         .allowlist_type("libseat")
         .allowlist_type("xkb_.*")
         .allowlist_type(".*va_list.*")